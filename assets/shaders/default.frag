@@ -0,0 +1,48 @@
+#version 330 core
+in vec2 frag_uv;
+flat in float frag_layer;
+in vec3 frag_normal;
+in vec2 frag_light;
+in vec4 frag_light_space;
+out vec4 final_color;
+
+uniform vec3 color;
+//One of the VIEW_ defines
+uniform int view_mode;
+uniform sampler2DArray block_textures;
+
+uniform vec3 sun_direction;
+uniform vec3 sun_color;
+uniform vec3 ambient_color;
+uniform sampler2DShadow shadow_map;
+
+#include "lighting.glsl"
+
+void main() {
+  if (view_mode == VIEW_FLAT) {
+    final_color = vec4(color, 1.0);
+    return;
+  }
+  if (view_mode == VIEW_NORMALS) {
+    final_color = vec4(normalize(frag_normal) * 0.5 + 0.5, 1.0);
+    return;
+  }
+  if (view_mode == VIEW_OVERDRAW) {
+    //Blended additively, so the brighter a pixel the more times it was drawn
+    final_color = vec4(0.1, 0.04, 0.01, 1.0);
+    return;
+  }
+
+  vec4 base = texture(block_textures, vec3(frag_uv, frag_layer));
+  //Cut out blocks like leaves have fully transparent holes
+  if (base.a < 0.1) {
+    discard;
+  }
+
+  float facing = dot(normalize(frag_normal), normalize(sun_direction));
+  float diffuse = max(facing, 0.0) * sun_visibility(shadow_map, frag_light_space, max(facing, 0.0));
+  vec3 sky = (ambient_color + sun_color * diffuse) * light_curve(frag_light.x);
+  vec3 light = sky + block_light_color * light_curve(frag_light.y);
+
+  final_color = vec4(base.rgb * light * color, base.a);
+}
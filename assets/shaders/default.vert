@@ -0,0 +1,27 @@
+#version 330 core
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform mat4 light_view_projection;
+
+layout (location = 0) in vec3 pos;
+layout (location = 1) in vec2 uv;
+layout (location = 2) in float layer;
+layout (location = 3) in vec3 normal;
+layout (location = 4) in vec2 light;
+
+out vec2 frag_uv;
+flat out float frag_layer;
+out vec3 frag_normal;
+out vec2 frag_light;
+out vec4 frag_light_space;
+
+void main() {
+  vec4 world_pos = model * vec4(pos, 1.0);
+  gl_Position = projection * view * world_pos;
+  frag_light_space = light_view_projection * world_pos;
+  frag_uv = uv;
+  frag_layer = layer;
+  frag_normal = mat3(model) * normal;
+  frag_light = light;
+}
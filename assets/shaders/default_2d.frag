@@ -0,0 +1,11 @@
+#version 330 core
+
+in vec2 frag_uv;
+in vec4 frag_tint;
+out vec4 color;
+
+uniform sampler2D sprite_texture;
+
+void main() {
+    color = texture(sprite_texture, frag_uv) * frag_tint;
+}
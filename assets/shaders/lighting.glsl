@@ -0,0 +1,28 @@
+//Shared lighting helpers, included by any shader that lights blocks. Expects MAX_LIGHT to be defined
+
+//Warm light given off by emissive blocks
+const vec3 block_light_color = vec3(1.0, 0.85, 0.6);
+
+//Each level of voxel light is a fixed fraction dimmer than the one above it
+float light_curve(float level) {
+  return pow(0.8, float(MAX_LIGHT) * (1.0 - level));
+}
+
+//1.0 fully lit, 0.0 fully shadowed, averaged over a 3x3 block of shadow map texels
+float sun_visibility(sampler2DShadow shadow_map, vec4 light_space, float facing) {
+  vec3 coords = light_space.xyz / light_space.w * 0.5 + 0.5;
+  if (coords.z > 1.0) {
+    return 1.0;
+  }
+
+  //Surfaces at a glancing angle to the sun need more bias to avoid shadow acne
+  float bias = mix(0.004, 0.0005, facing);
+  vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0));
+  float visibility = 0.0;
+  for (int x = -1; x <= 1; x++) {
+    for (int y = -1; y <= 1; y++) {
+      visibility += texture(shadow_map, vec3(coords.xy + vec2(x, y) * texel, coords.z - bias));
+    }
+  }
+  return visibility / 9.0;
+}
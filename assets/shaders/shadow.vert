@@ -0,0 +1,9 @@
+#version 330 core
+uniform mat4 model;
+uniform mat4 light_view_projection;
+
+layout (location = 0) in vec3 pos;
+
+void main() {
+  gl_Position = light_view_projection * model * vec4(pos, 1.0);
+}
@@ -0,0 +1,24 @@
+#version 330 core
+
+in vec3 view_ray;
+out vec4 final_color;
+
+uniform vec3 sky_zenith;
+uniform vec3 sky_horizon;
+uniform vec3 sun_direction;
+uniform vec3 sun_color;
+uniform vec3 up;
+
+void main() {
+  vec3 ray = normalize(view_ray);
+  float height = dot(ray, up);
+
+  // Horizon colour at and below the horizon, fading to the zenith colour overhead
+  vec3 sky = mix(sky_horizon, sky_zenith, smoothstep(0.0, 0.6, height));
+
+  // A soft sun disc, which disappears along with the sun's light at night
+  float sun = smoothstep(0.9990, 0.9995, dot(ray, normalize(sun_direction)));
+  sky += sun_color * sun;
+
+  final_color = vec4(sky, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+
+uniform mat4 inverse_view_projection;
+
+out vec3 view_ray;
+
+void main() {
+  // One triangle covering the whole screen, generated from the vertex ID
+  vec2 corner = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+  gl_Position = vec4(corner, 1.0, 1.0);
+
+  vec4 far_point = inverse_view_projection * vec4(corner, 1.0, 1.0);
+  vec4 near_point = inverse_view_projection * vec4(corner, -1.0, 1.0);
+  view_ray = far_point.xyz / far_point.w - near_point.xyz / near_point.w;
+}
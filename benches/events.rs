@@ -0,0 +1,50 @@
+//Event dispatch with growing numbers of listeners. Output is the same as the other benches, see
+//benches/world.rs
+use std::cell::RefCell;
+use std::rc::Rc;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use kardashev::events::{Event, EventHandler, EventQueue, EventType};
+
+const EVENTS_PER_DISPATCH: i32 = 64;
+
+struct CountingHandler {
+    count: usize
+}
+
+impl EventHandler for CountingHandler {
+    fn on_event(&mut self, event: &Event) {
+        black_box(event);
+        self.count += 1;
+    }
+
+    fn event_types(&self) -> Vec<EventType> {
+        vec![EventType::ChunkUnloaded]
+    }
+}
+
+fn dispatch_events(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dispatch_events");
+    for listeners in [1, 16, 256] {
+        let mut queue = EventQueue::new();
+        for _ in 0..listeners {
+            queue.register_handler(Rc::new(RefCell::new(CountingHandler { count: 0 })));
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(listeners), &listeners, |b, _| {
+            b.iter_batched_ref(
+                || (0..EVENTS_PER_DISPATCH).map(|x| Event::ChunkUnloaded((x, 0, 0))).collect::<Vec<_>>(),
+                |events| {
+                    for event in events.drain(..) {
+                        queue.push_event(event);
+                    }
+                    queue.dispatch_events();
+                },
+                BatchSize::SmallInput
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, dispatch_events);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//The CPU half of chunk meshing, everything up to the point the vertices are uploaded. Output is
+//the same as the other benches, see benches/world.rs
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use glam::Vec3;
+use kardashev::events::EventQueue;
+use kardashev::world::block::BlockRegistry;
+use kardashev::world::chunk_mesh_manager::build_chunk_mesh;
+use kardashev::world::{world_to_chunk_pos, World};
+
+const SEED: u32 = 1234;
+
+fn chunk_meshing(c: &mut Criterion) {
+    let registry = BlockRegistry::new();
+    let mut world = World::with_seed(SEED);
+    world.update(Vec3::ZERO, &mut EventQueue::new());
+
+    //The surface chunk has the most faces, with grass, dirt and stone all exposed somewhere
+    let pos = world_to_chunk_pos(Vec3::ZERO);
+    let blocks = world.chunks[&pos].blocks;
+    let light = world.light.padded(pos);
+
+    c.bench_function("build_chunk_mesh", |b| {
+        b.iter(|| build_chunk_mesh(black_box(&blocks), &light, &registry))
+    });
+}
+
+criterion_group!(benches, chunk_meshing);
+criterion_main!(benches);
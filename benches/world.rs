@@ -0,0 +1,51 @@
+//World generation and streaming. Criterion writes its estimates for every benchmark as JSON under
+//target/criterion/<group>/<benchmark>/new/estimates.json, and `cargo bench -- --output-format bencher`
+//prints one line per benchmark, either of which can be kept to compare between commits
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use glam::Vec3;
+use kardashev::events::EventQueue;
+use kardashev::world::chunk::{Chunk, CHUNK_SIZE, VOXEL_SIZE};
+use kardashev::world::{chunk_range, World, DEFAULT_RENDER_DISTANCE};
+use noise::Perlin;
+
+const SEED: u32 = 1234;
+
+fn chunk_generation(c: &mut Criterion) {
+    let perlin = Perlin::new(SEED);
+    c.bench_function("chunk_from_perlin_noise", |b| {
+        b.iter(|| Chunk::from_perlin_noise(black_box((3, 0, -2)), &perlin))
+    });
+}
+
+fn chunk_ranges(c: &mut Criterion) {
+    c.bench_function("chunk_range", |b| {
+        b.iter(|| chunk_range(black_box((5, -1, 7)), DEFAULT_RENDER_DISTANCE).count())
+    });
+}
+
+//Walking one chunk along x from a world that is already loaded around the player, which unloads a
+//slab of chunks behind them and generates and lights a new slab ahead
+fn world_update_across_boundary(c: &mut Criterion) {
+    let chunk_length = CHUNK_SIZE as f32 * VOXEL_SIZE;
+    let start = Vec3::splat(chunk_length * 0.5);
+    let next = start + Vec3::X * chunk_length;
+
+    c.bench_function("world_update_across_chunk_boundary", |b| {
+        b.iter_batched(
+            || {
+                let mut world = World::with_seed(SEED);
+                let mut events = EventQueue::new();
+                world.update(start, &mut events);
+                (world, EventQueue::new())
+            },
+            |(mut world, mut events)| {
+                world.update(next, &mut events);
+                (world, events)
+            },
+            BatchSize::LargeInput
+        )
+    });
+}
+
+criterion_group!(benches, chunk_generation, chunk_ranges, world_update_across_boundary);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::FromUtf8Error;
+use thiserror::Error;
+use tracing::debug;
+
+//Environment variable holding extra mod directories, separated like PATH
+const MODS_ENVIRONMENT_VARIABLE: &str = "KARDASHEV_MODS";
+
+//Assets the game can't start without are compiled in, so a missing or broken assets directory
+//still gets us as far as the main menu
+macro_rules! embed_assets {
+    ($($name:literal),* $(,)?) => {
+        &[$(($name, include_bytes!(concat!("../../assets/", $name)) as &[u8])),*]
+    };
+}
+
+const EMBEDDED: &[(&str, &[u8])] = embed_assets!(
+    "shaders/default.vert",
+    "shaders/default.frag",
+    "shaders/default_2d.vert",
+    "shaders/default_2d.frag",
+    "shaders/lighting.glsl",
+    "shaders/shadow.vert",
+    "shaders/shadow.frag",
+    "shaders/sky.vert",
+    "shaders/sky.frag",
+    "fonts/FiraCode-SemiBold.tff",
+);
+
+#[derive(Debug, Error)]
+pub enum AssetError {
+    #[error("Missing asset {name}: not found in [{}] and not built in", display_paths(.searched))]
+    Missing { name: String, searched: Vec<PathBuf> },
+    #[error("Unable to read asset {name} from {}", .path.display())]
+    Read { name: String, path: PathBuf, #[source] source: io::Error },
+    #[error("Asset {name} is not valid UTF-8")]
+    NotUtf8 { name: String, #[source] source: FromUtf8Error }
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", ")
+}
+
+//Finds assets by name, like shaders/sky.frag, wherever they live. Mod directories are searched
+//first so they can override anything, then the assets directory, then what was built in
+pub struct Assets {
+    directories: Vec<PathBuf>
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        let exe_directory = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let mut directories = Vec::new();
+
+        //Every folder in mods/ next to the executable is a mod, applied in name order
+        if let Some(Ok(entries)) = exe_directory.as_ref().map(|dir| fs::read_dir(dir.join("mods"))) {
+            let mut mods: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+            mods.sort();
+            directories.extend(mods);
+        }
+        if let Some(paths) = std::env::var_os(MODS_ENVIRONMENT_VARIABLE) {
+            directories.extend(std::env::split_paths(&paths));
+        }
+
+        //Release builds ship assets next to the executable, while cargo run finds them in the
+        //source tree. The working directory is never searched, so launching from somewhere with an
+        //unrelated assets folder can't shadow the game's own
+        let candidates = [
+            exe_directory.map(|dir| dir.join("assets")),
+            Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets")),
+        ];
+        if let Some(assets) = candidates.into_iter().flatten().find(|dir| dir.is_dir()) {
+            directories.push(assets);
+        }
+
+        for directory in &directories {
+            debug!("Searching {} for assets.", directory.display());
+        }
+
+        Self {
+            directories
+        }
+    }
+
+    //The file on disk an asset would be read from, if it isn't just built in
+    pub fn path(&self, name: &str) -> Option<PathBuf> {
+        self.directories.iter().map(|dir| dir.join(name)).find(|path| path.is_file())
+    }
+
+    pub fn read(&self, name: &str) -> Result<Cow<'static, [u8]>, AssetError> {
+        if let Some(path) = self.path(name) {
+            debug!("Reading asset {} from {}.", name, path.display());
+            return match fs::read(&path) {
+                Ok(bytes) => Ok(Cow::Owned(bytes)),
+                Err(source) => Err(AssetError::Read { name: name.to_string(), path, source })
+            };
+        }
+
+        if let Some((_, bytes)) = EMBEDDED.iter().find(|(embedded, _)| *embedded == name) {
+            debug!("Using built in asset {}.", name);
+            return Ok(Cow::Borrowed(*bytes));
+        }
+
+        Err(AssetError::Missing { name: name.to_string(), searched: self.directories.clone() })
+    }
+
+    pub fn read_to_string(&self, name: &str) -> Result<String, AssetError> {
+        let bytes = self.read(name)?;
+        String::from_utf8(bytes.into_owned()).map_err(|source| AssetError::NotUtf8 { name: name.to_string(), source })
+    }
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
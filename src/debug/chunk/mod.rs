@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use glam::Vec3;
+use sdl2::pixels::Color;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+use crate::events::{Event, EventQueue};
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::world::chunk::chunk_bounds;
+use crate::world::chunk_mesh_manager::ChunkMeshManager;
+use crate::world::{world_to_chunk_pos, ChunkPos, World};
+use super::draw;
+use super::panels::{DebugPanel, PanelContext};
+
+//Chunks pass through every state but Meshed within a single update, so each change is held on
+//screen for a while to be seen at all
+const STATE_HOLD: Duration = Duration::from_millis(500);
+const MINIMAP_CELL: f32 = 6.0;
+const MINIMAP_MARGIN: f32 = 8.0;
+//Pulls each box in slightly so neighbouring chunks' edges don't draw over each other
+const BOX_INSET: f32 = 0.02;
+
+#[derive(Clone, Copy, Debug, Display, EnumIter, Eq, Hash, PartialEq)]
+pub enum ChunkState {
+    //Generated and lit, waiting for its mesh
+    Generated,
+    Meshed,
+    //Its blocks or light changed and its mesh hasn't caught up
+    Dirty,
+    //Out of the load radius, with its mesh still to be removed
+    Unloading
+}
+
+impl ChunkState {
+    pub fn color(&self) -> Color {
+        match self {
+            ChunkState::Generated => Color::YELLOW,
+            ChunkState::Meshed => Color::GREEN,
+            ChunkState::Dirty => Color::RGB(255, 128, 0),
+            ChunkState::Unloading => Color::RED
+        }
+    }
+
+    //Which state a minimap column shows when its chunks disagree, most interesting first
+    fn priority(&self) -> u8 {
+        match self {
+            ChunkState::Dirty => 3,
+            ChunkState::Generated => 2,
+            ChunkState::Unloading => 1,
+            ChunkState::Meshed => 0
+        }
+    }
+}
+
+//The state of every chunk around the player, worked out from the world, the meshes and the
+//events still waiting to be dispatched
+pub struct ChunkStates {
+    states: HashMap<ChunkPos, (ChunkState, Instant)>,
+    center: ChunkPos,
+    render_distance: i32
+}
+
+impl ChunkStates {
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+            center: (0, 0, 0),
+            render_distance: 0
+        }
+    }
+
+    //Call between updating the world and dispatching its events, while the changes are still pending
+    pub fn observe(&mut self, world: &World, meshes: &ChunkMeshManager, events: &EventQueue, player: Vec3) {
+        self.center = world_to_chunk_pos(player);
+        self.render_distance = world.render_distance;
+
+        let mut observed = HashMap::new();
+        for &pos in world.chunks.keys() {
+            let state = if meshes.has_mesh(pos) { ChunkState::Meshed } else { ChunkState::Generated };
+            observed.insert(pos, state);
+        }
+        for (&pos, _) in meshes.iter() {
+            observed.entry(pos).or_insert(ChunkState::Unloading);
+        }
+        for event in events.pending_events() {
+            if let Event::ChunkModified(pos, ..) = event
+                && let Some(state @ ChunkState::Meshed) = observed.get_mut(pos) {
+                *state = ChunkState::Dirty;
+            }
+        }
+
+        let now = Instant::now();
+        self.states.retain(|pos, (state, since)| {
+            observed.contains_key(pos) || (*state == ChunkState::Unloading && since.elapsed() < STATE_HOLD)
+        });
+        for (pos, state) in observed {
+            match self.states.get(&pos) {
+                Some((shown, _)) if *shown == state => {}
+                //Settling back to meshed waits until the last change has been seen
+                Some((_, since)) if state == ChunkState::Meshed && since.elapsed() < STATE_HOLD => {}
+                _ => {
+                    self.states.insert(pos, (state, now));
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, pos: ChunkPos) -> Option<ChunkState> {
+        self.states.get(&pos).map(|(state, _)| *state)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkPos, ChunkState)> + '_ {
+        self.states.iter().map(|(&pos, &(state, _))| (pos, state))
+    }
+
+    pub fn count(&self, state: ChunkState) -> usize {
+        self.states.values().filter(|(shown, _)| *shown == state).count()
+    }
+}
+
+impl Default for ChunkStates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//Chunk borders coloured by state in the world, the current chunk and load radius, and a top down
+//minimap of the chunks around the player
+pub struct ChunkPanel;
+
+impl DebugPanel for ChunkPanel {
+    fn title(&self) -> &'static str {
+        "Chunks"
+    }
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String> {
+        let states = &ctx.session.chunk_states;
+        let counts: Vec<String> = ChunkState::iter().map(|state| format!("{} {}", state, states.count(state))).collect();
+        vec![
+            format!("Chunk: ({}, {}, {})  Load radius: {}", states.center.0, states.center.1, states.center.2, states.render_distance),
+            counts.join("  ")
+        ]
+    }
+
+    fn extra_draws(&self, ctx: &PanelContext) -> Vec<Draw2D> {
+        let states = &ctx.session.chunk_states;
+        for (pos, state) in states.iter() {
+            let (min, max) = chunk_bounds(pos);
+            draw::aabb(min + Vec3::splat(BOX_INSET), max - Vec3::splat(BOX_INSET), state.color());
+        }
+
+        let radius = states.render_distance;
+        let (cx, cy, cz) = states.center;
+        let (load_min, _) = chunk_bounds((cx - radius, cy - radius, cz - radius));
+        let (_, load_max) = chunk_bounds((cx + radius, cy + radius, cz + radius));
+        draw::aabb(load_min, load_max, Color::WHITE);
+        let (min, max) = chunk_bounds(states.center);
+        let center = (min + max) * 0.5;
+        draw::text(center, format!("({}, {}, {})", cx, cy, cz), Color::WHITE);
+
+        self.minimap(states)
+    }
+}
+
+impl ChunkPanel {
+    //One cell per column of chunks, looking down with +x to the right and +z down the screen
+    fn minimap(&self, states: &ChunkStates) -> Vec<Draw2D> {
+        let radius = states.render_distance;
+        let (cx, _, cz) = states.center;
+        let mut columns: HashMap<(i32, i32), ChunkState> = HashMap::new();
+        for ((x, _, z), state) in states.iter() {
+            let column = columns.entry((x - cx, z - cz)).or_insert(state);
+            if state.priority() > column.priority() {
+                *column = state;
+            }
+        }
+
+        let side = (radius * 2 + 1) as f32 * MINIMAP_CELL;
+        //Anchored to the right edge, so offsets run leftwards from the map's right hand side
+        let cell_position = |x: i32, z: i32| (
+            -MINIMAP_MARGIN - (radius - x) as f32 * MINIMAP_CELL,
+            (z + radius) as f32 * MINIMAP_CELL - side / 2.0
+        );
+
+        let mut draws = vec![Draw2D::rect((-MINIMAP_MARGIN, 0.0), (side, side), Color::RGBA(0, 0, 0, 160)).anchored(Anchor::Right)];
+        for ((x, z), state) in columns {
+            if x.abs() > radius || z.abs() > radius {
+                continue;
+            }
+            let (px, py) = cell_position(x, z);
+            draws.push(Draw2D::rect((px, py + MINIMAP_CELL / 2.0), (MINIMAP_CELL - 1.0, MINIMAP_CELL - 1.0), state.color()).anchored(Anchor::Right).on_layer(1));
+        }
+        let (px, py) = cell_position(0, 0);
+        draws.push(Draw2D::rect((px, py + MINIMAP_CELL / 2.0), (MINIMAP_CELL - 1.0, MINIMAP_CELL - 1.0), Color::WHITE).anchored(Anchor::Right).on_layer(2));
+        draws
+    }
+}
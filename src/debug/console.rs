@@ -0,0 +1,368 @@
+use glam::Vec3;
+use sdl2::pixels::Color;
+use thiserror::Error;
+use tracing::Level;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::reload;
+use crate::error::report;
+use crate::logging::{LogBuffer, LogFilter, LogRecord};
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::rendering::text::FontId;
+use crate::state::in_game::GameSession;
+use crate::world::block;
+use crate::world::MAX_RENDER_DISTANCE;
+use super::DebugOverlay;
+
+//Lines of log shown above the input line
+const CONSOLE_LINES: usize = 16;
+pub const CONSOLE_TARGET: &str = "console";
+const HISTORY_LIMIT: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Unknown command {0}, try help")]
+    Unknown(String),
+    #[error("Usage: {0}")]
+    Usage(&'static str),
+    #[error("{0} isn't a number")]
+    Number(String),
+    #[error("Unknown block {name}, expected one of: {}", block::names().collect::<Vec<_>>().join(", "))]
+    UnknownBlock { name: String },
+    #[error("Block ({}, {}, {}) isn't in a loaded chunk", .0.0, .0.1, .0.2)]
+    NotLoaded((i32, i32, i32)),
+    #[error("Render distance must be between 1 and {MAX_RENDER_DISTANCE}, got {0}")]
+    RenderDistance(i32),
+    #[error("{0} isn't a log level, expected trace, debug, info, warn or error")]
+    Level(String),
+    #[error("Invalid log filter")]
+    LogFilter(#[from] ParseError),
+    #[error("Unable to apply the log filter")]
+    ReloadFilter(#[from] reload::Error)
+}
+
+//What a command can reach. Commands only run in game, so there is always a session
+pub struct CommandContext<'a> {
+    pub session: &'a mut GameSession,
+    pub debugger: &'a mut DebugOverlay
+}
+
+//Runs with the arguments after the command name, returning a line to print on success
+pub type CommandFn = fn(&[&str], &mut CommandContext) -> Result<String, CommandError>;
+
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub run: CommandFn
+}
+
+pub struct CommandRegistry {
+    commands: Vec<Command>
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self { commands: Vec::new() }
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Command { name: "tp", usage: "tp <x> <y> <z>", run: teleport });
+        registry.register(Command { name: "seed", usage: "seed", run: seed });
+        registry.register(Command { name: "setblock", usage: "setblock <x> <y> <z> <block>", run: set_block });
+        registry.register(Command { name: "render_distance", usage: "render_distance <chunks>", run: render_distance });
+        registry.register(Command { name: "log", usage: "log <target>=<level> | log <level>", run: log_level });
+        registry.register(Command { name: "logview", usage: "logview <level> [target] | logview off", run: log_view });
+        registry
+    }
+
+    //A later command with the same name replaces the earlier one
+    pub fn register(&mut self, command: Command) {
+        self.commands.retain(|existing| existing.name != command.name);
+        self.commands.push(command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    //Every command name starting with the prefix, help included
+    pub fn complete(&self, prefix: &str) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = std::iter::once("help")
+            .chain(self.commands.iter().map(|command| command.name))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn usages(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.commands.iter().map(|command| command.usage)
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//A drop-down console. Its scrollback is the in-game log, with commands and their results written
+//into the same buffer so everything reads in the order it happened
+pub struct Console {
+    open: bool,
+    input: String,
+    history: Vec<String>,
+    //Which history entry is in the input line while browsing with up and down
+    history_cursor: Option<usize>,
+    //How many lines back from the newest the view is scrolled
+    scroll: usize,
+    log: LogBuffer,
+    commands: CommandRegistry
+}
+
+impl Console {
+    pub fn new(log: LogBuffer) -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            scroll: 0,
+            log,
+            commands: CommandRegistry::with_builtins()
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.scroll = 0;
+    }
+
+    pub fn commands_mut(&mut self) -> &mut CommandRegistry {
+        &mut self.commands
+    }
+
+    pub fn push_char(&mut self, character: char) {
+        self.input.push(character);
+        self.history_cursor = None;
+    }
+
+    pub fn erase(&mut self) {
+        self.input.pop();
+        self.history_cursor = None;
+    }
+
+    //Negative steps go back to older entries, positive forward to newer ones and then an empty line
+    pub fn browse_history(&mut self, step: i32) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let cursor = match (self.history_cursor, step < 0) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(cursor), true) => Some(cursor.saturating_sub(1)),
+            (Some(cursor), false) if cursor + 1 < self.history.len() => Some(cursor + 1),
+            (Some(_), false) => None
+        };
+        self.history_cursor = cursor;
+        self.input = cursor.map(|cursor| self.history[cursor].clone()).unwrap_or_default();
+    }
+
+    pub fn scroll(&mut self, lines: i32) {
+        let total = self.lines().len();
+        self.scroll = self.scroll.saturating_add_signed(lines as isize).min(total.saturating_sub(CONSOLE_LINES));
+    }
+
+    //Completes the command name. With several matches it fills in what they share and lists them
+    pub fn complete(&mut self) {
+        if self.input.contains(' ') {
+            return;
+        }
+
+        let matches = self.commands.complete(&self.input);
+        match matches.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{} ", name),
+            [first, ..] => {
+                let shared = matches.iter().fold(first.len(), |shared, name| {
+                    first.bytes().zip(name.bytes()).take(shared).take_while(|(a, b)| a == b).count()
+                });
+                self.input = first[..shared].to_string();
+                self.print(Level::INFO, matches.join("  "));
+            }
+        }
+    }
+
+    pub fn submit(&mut self, ctx: &mut CommandContext) {
+        let line = std::mem::take(&mut self.input);
+        self.history_cursor = None;
+        self.scroll = 0;
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+
+        self.print(Level::INFO, format!("> {}", line));
+        if self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.to_string());
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.remove(0);
+            }
+        }
+
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let arguments: Vec<&str> = words.collect();
+
+        if name == "help" {
+            let usages: Vec<&str> = self.commands.usages().collect();
+            self.print(Level::INFO, format!("Commands:\n{}", usages.join("\n")));
+            return;
+        }
+
+        let result = match self.commands.get(name) {
+            Some(command) => (command.run)(&arguments, ctx),
+            None => Err(CommandError::Unknown(name.to_string()))
+        };
+        match result {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => self.print(Level::INFO, output),
+            Err(error) => self.print(Level::WARN, report(&error))
+        }
+    }
+
+    fn print(&self, level: Level, message: String) {
+        self.log.push(LogRecord::new(level, CONSOLE_TARGET, message));
+    }
+
+    //Every record split into the lines it shows as, oldest first
+    fn lines(&self) -> Vec<(Color, String)> {
+        let mut lines = Vec::new();
+        for record in self.log.records() {
+            let color = level_color(&record);
+            for (index, line) in record.to_string().lines().enumerate() {
+                let line = match (index, record.target.as_str()) {
+                    (_, CONSOLE_TARGET) | (1.., _) => line.to_string(),
+                    (0, target) => format!("{} {}: {}", record.level, target, line)
+                };
+                lines.push((color, line));
+            }
+        }
+        lines
+    }
+
+    pub fn draws(&self, font: FontId, line_height: f32, screen_width: f32) -> Vec<Draw2D> {
+        if !self.open {
+            return Vec::new();
+        }
+
+        let height = line_height * (CONSOLE_LINES + 1) as f32;
+        let mut draws = vec![Draw2D::rect((0.0, 0.0), (screen_width, height), Color::RGBA(10, 10, 20, 220)).anchored(Anchor::TopLeft).on_layer(3)];
+
+        let lines = self.lines();
+        let end = lines.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(CONSOLE_LINES);
+        for (row, (color, line)) in lines[start..end].iter().enumerate() {
+            draws.push(Draw2D::text(font, line.as_str(), (0.0, line_height * row as f32), *color).on_layer(4));
+        }
+
+        let input = format!("> {}_", self.input);
+        draws.push(Draw2D::text(font, input, (0.0, line_height * CONSOLE_LINES as f32), Color::CYAN).on_layer(4));
+        draws
+    }
+}
+
+fn level_color(record: &LogRecord) -> Color {
+    match record.level {
+        Level::ERROR => Color::RED,
+        Level::WARN => Color::YELLOW,
+        _ if record.target == CONSOLE_TARGET => Color::CYAN,
+        Level::INFO => Color::WHITE,
+        _ => Color::GRAY
+    }
+}
+
+fn parse<T: std::str::FromStr>(argument: &str) -> Result<T, CommandError> {
+    argument.parse().map_err(|_| CommandError::Number(argument.to_string()))
+}
+
+//Positions are in world units, the same as the camera readout in the overlay
+fn teleport(arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    let [x, y, z] = arguments else {
+        return Err(CommandError::Usage("tp <x> <y> <z>"));
+    };
+    let position = Vec3::new(parse(x)?, parse(y)?, parse(z)?);
+    ctx.session.camera.set_position(position);
+    Ok(format!("Teleported to ({:.1}, {:.1}, {:.1})", position.x, position.y, position.z))
+}
+
+fn seed(_arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    Ok(format!("Seed: {}", ctx.session.world.seed))
+}
+
+//Positions are in blocks, not world units
+fn set_block(arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    let [x, y, z, name] = arguments else {
+        return Err(CommandError::Usage("setblock <x> <y> <z> <block>"));
+    };
+    let position = (parse(x)?, parse(y)?, parse(z)?);
+    let id = block::by_name(name).ok_or_else(|| CommandError::UnknownBlock { name: name.to_string() })?;
+
+    let session = &mut *ctx.session;
+    if !session.world.set_block(position, id, &mut session.event_queue) {
+        return Err(CommandError::NotLoaded(position));
+    }
+    Ok(format!("Set ({}, {}, {}) to {}", position.0, position.1, position.2, name))
+}
+
+fn render_distance(arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    let [distance] = arguments else {
+        return Err(CommandError::Usage("render_distance <chunks>"));
+    };
+    let distance: i32 = parse(distance)?;
+    if !(1..=MAX_RENDER_DISTANCE).contains(&distance) {
+        return Err(CommandError::RenderDistance(distance));
+    }
+    ctx.session.world.render_distance = distance;
+    Ok(format!("Render distance set to {} chunks", distance))
+}
+
+//Targets without a crate path are taken as modules of the game, so `log world=debug` is the same
+//as `log kardashev::world=debug`
+fn log_level(arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    let [directive] = arguments else {
+        return Err(CommandError::Usage("log <target>=<level> | log <level>"));
+    };
+    let directive = match directive.split_once('=') {
+        Some((target, level)) if !target.contains("::") && target != "kardashev" => format!("kardashev::{}={}", target, level),
+        _ => directive.to_string()
+    };
+    ctx.debugger.set_log_level(&directive)?;
+    Ok(format!("Logging {}", directive))
+}
+
+//Filters the overlay's log view, rather than what gets logged in the first place
+fn log_view(arguments: &[&str], ctx: &mut CommandContext) -> Result<String, CommandError> {
+    let (level, target) = match arguments {
+        ["off"] => {
+            ctx.debugger.log_view = None;
+            return Ok("Log view hidden".to_string());
+        }
+        [level] => (*level, None),
+        [level, target] => (*level, Some(*target)),
+        _ => return Err(CommandError::Usage("logview <level> [target] | logview off"))
+    };
+
+    let level = level.parse::<Level>().map_err(|_| CommandError::Level(level.to_string()))?;
+    let filter = match target {
+        Some(target) => LogFilter::new(level).with_target(target),
+        None => LogFilter::new(level)
+    };
+    ctx.debugger.log_view = Some(filter);
+    Ok(format!("Showing {} and above in the log view, of whatever log lets through", level))
+}
@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use glam::Vec3;
+use sdl2::pixels::Color;
+
+thread_local! {
+    //Filled by anything during a frame and emptied by the renderer when it draws them, the same
+    //way the profiler collects scopes without being passed around
+    static DRAWS: RefCell<DebugDrawList> = RefCell::new(DebugDrawList::default());
+}
+
+//World space shapes waiting to be drawn. Lines are drawn over everything rather than depth tested,
+//so they can be seen through terrain
+#[derive(Default)]
+pub struct DebugDrawList {
+    pub lines: Vec<(Vec3, Vec3, Color)>,
+    pub texts: Vec<(Vec3, String, Color)>
+}
+
+impl DebugDrawList {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.texts.is_empty()
+    }
+}
+
+//Drawn for this frame only, so call it again every frame it should stay on screen
+pub fn line(from: Vec3, to: Vec3, color: Color) {
+    DRAWS.with_borrow_mut(|draws| draws.lines.push((from, to, color)));
+}
+
+//The twelve edges of an axis aligned box
+pub fn aabb(min: Vec3, max: Vec3, color: Color) {
+    let corner = |x: bool, y: bool, z: bool| Vec3::new(
+        if x { max.x } else { min.x },
+        if y { max.y } else { min.y },
+        if z { max.z } else { min.z }
+    );
+
+    DRAWS.with_borrow_mut(|draws| {
+        for a in [false, true] {
+            for b in [false, true] {
+                draws.lines.push((corner(false, a, b), corner(true, a, b), color));
+                draws.lines.push((corner(a, false, b), corner(a, true, b), color));
+                draws.lines.push((corner(a, b, false), corner(a, b, true), color));
+            }
+        }
+    });
+}
+
+//Text whose top left corner sits on the given world position
+pub fn text(position: Vec3, text: impl Into<String>, color: Color) {
+    DRAWS.with_borrow_mut(|draws| draws.texts.push((position, text.into(), color)));
+}
+
+//Everything submitted since the last call
+pub fn take() -> DebugDrawList {
+    DRAWS.with_borrow_mut(std::mem::take)
+}
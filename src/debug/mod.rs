@@ -1,64 +1,162 @@
-mod chunk;
+pub mod chunk;
+pub mod console;
+pub mod draw;
+pub mod panels;
+pub mod profiler;
+pub mod toasts;
 
-use std::collections::HashSet;
-use std::time::Duration;
-use glam::Vec3;
+use std::collections::{BTreeMap, HashSet};
+use sdl2::pixels::Color;
+use tracing::{debug, info, warn, Level};
+use tracing_subscriber::filter::Directive;
 use tracing_subscriber::{reload, EnvFilter};
-use crate::Camera;
+use crate::rendering::debug_view::RenderDebugMode;
+use crate::error::report;
+use crate::logging::{LogBuffer, LogFilter};
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::rendering::text::FontId;
+use console::CommandError;
+use panels::{DebugPanel, PanelContext};
+use toasts::Toasts;
+
+//Most recent matching records shown by the log view
+const LOG_VIEW_LINES: usize = 12;
 
 pub struct DebugOverlay {
     pub filter_handle: reload::Handle<EnvFilter, tracing_subscriber::Registry>,
-    pub visible_modules: HashSet<&'static str>,
-    pub frame_time_ms: f64,
-    pub chunk_count: usize,
-    pub draw_calls: usize,
-    pub camera_position: Vec3,
-    pub camera_pitch: f32,
-    pub camera_yaw: f32,
+    //The filter logging started with, from RUST_LOG or the default, which console levels go on top of
+    base_log_filter: String,
+    //Panels by the number of the F-key that toggles them, in the order they are drawn
+    panels: BTreeMap<i32, Box<dyn DebugPanel>>,
+    pub visible_panels: HashSet<i32>,
+    //Levels set from the console, by target, with an empty target for the default level
+    pub log_levels: BTreeMap<String, String>,
+    pub render_mode: RenderDebugMode,
+    pub log: LogBuffer,
+    //What the log view shows, or None when it is hidden
+    pub log_view: Option<LogFilter>,
+    pub toasts: Toasts,
 }
 
 impl DebugOverlay {
-    pub fn new(filter_handle: reload::Handle<EnvFilter, tracing_subscriber::Registry>) -> Self {
-        Self {
+    pub fn new(filter_handle: reload::Handle<EnvFilter, tracing_subscriber::Registry>, base_log_filter: String, log: LogBuffer) -> Self {
+        let mut overlay = Self {
             filter_handle,
-            visible_modules: HashSet::new(),
-            frame_time_ms: 0.0,
-            chunk_count: 0,
-            draw_calls: 0,
-            camera_position: Vec3::ZERO,
-            camera_pitch: 0.0,
-            camera_yaw: 0.0
+            base_log_filter,
+            panels: BTreeMap::new(),
+            visible_panels: HashSet::new(),
+            log_levels: BTreeMap::new(),
+            render_mode: RenderDebugMode::Off,
+            toasts: Toasts::new(log.clone()),
+            log,
+            log_view: None
+        };
+        panels::register_builtin(&mut overlay);
+        overlay
+    }
+
+    //Replaces whatever panel was on that key before
+    pub fn register_panel(&mut self, key: i32, panel: Box<dyn DebugPanel>) {
+        debug!("Registering debug panel {} on F{}.", panel.title(), key);
+        self.panels.insert(key, panel);
+    }
+
+    pub fn toggle_panel(&mut self, key: i32) {
+        if !self.panels.contains_key(&key) {
+            debug!("No debug panel on F{}.", key);
+            return;
+        }
+        if !self.visible_panels.remove(&key) {
+            self.visible_panels.insert(key);
         }
     }
- 
-    pub fn toggle_module(&mut self, module: &'static str) {
-        if !self.visible_modules.remove(module) {
-            self.visible_modules.insert(module);
+
+    pub fn is_panel_visible(&self, key: i32) -> bool {
+        self.visible_panels.contains(&key)
+    }
+
+    //Visible panels stacked down the left of the screen from the given height, each under its title
+    pub fn panel_draws(&self, ctx: &PanelContext, font: FontId, top: f32, line_height: f32) -> Vec<Draw2D> {
+        let mut draws = Vec::new();
+        let mut y = top;
+        for (key, panel) in self.panels.iter().filter(|(key, _)| self.visible_panels.contains(key)) {
+            draws.push(Draw2D::text(font, format!("[F{}] {}", key, panel.title()), (0.0, y), Color::CYAN));
+            y += line_height;
+            for line in panel.lines(ctx) {
+                draws.push(Draw2D::text(font, line, (0.0, y), Color::WHITE));
+                y += line_height;
+            }
+            draws.extend(panel.extra_draws(ctx));
         }
+        draws
+    }
 
-        // Rebuild filter string
-        let mut filter_str = String::new();
-        for m in &self.visible_modules {
+    //Takes a directive like `kardashev::world=debug`, in the same syntax as RUST_LOG
+    pub fn set_log_level(&mut self, directive: &str) -> Result<(), CommandError> {
+        directive.parse::<Directive>()?;
+        let (target, level) = directive.split_once('=').unwrap_or(("", directive));
+        self.log_levels.insert(target.to_string(), level.to_string());
+        self.apply_filter()
+    }
+
+    fn apply_filter(&self) -> Result<(), CommandError> {
+        // Rebuild filter string from the startup filter. Later directives for the same target
+        // replace earlier ones, so console levels win over RUST_LOG without losing the rest of it
+        let mut filter_str = self.base_log_filter.clone();
+        let levels = self.log_levels.iter().map(|(target, level)| match target.as_str() {
+            "" => level.clone(),
+            _ => format!("{}={}", target, level)
+        });
+        for directive in levels {
             if !filter_str.is_empty() {
                 filter_str.push(',');
             }
-            filter_str.push_str(&format!("{}=debug", m));
+            filter_str.push_str(&directive);
         }
 
         // Apply to subscriber
-        let _ = self.filter_handle.modify(|filter| {
+        self.filter_handle.modify(|filter| {
             *filter = EnvFilter::new(filter_str);
-        });
+        })?;
+        Ok(())
+    }
+
+    pub fn cycle_render_mode(&mut self) {
+        self.render_mode = self.render_mode.next();
     }
 
-    pub fn is_module_visible(&self, module: &str) -> bool {
-        self.visible_modules.contains(module)
+    pub fn export_trace(&self) {
+        match profiler::export_chrome_trace() {
+            Ok(path) => info!("Exported the last {} frames to {}.", profiler::frame_times_ms().len(), path.display()),
+            Err(error) => warn!("Unable to export a trace: {}", report(&error))
+        }
     }
 
-    pub fn update(&mut self, frame_time: Duration, camera: &Camera) {
-        self.frame_time_ms = frame_time.as_secs_f64() * 1000.0;
-        self.camera_position = camera.position();
-        self.camera_pitch = camera.pitch();
-        self.camera_yaw = camera.yaw();
+    //Shows everything at info and above until the console narrows it down
+    pub fn toggle_log_view(&mut self) {
+        self.log_view = match self.log_view {
+            Some(_) => None,
+            None => Some(LogFilter::new(Level::INFO))
+        };
+    }
+
+    //The most recent records the log view's filter lets through, in the bottom right
+    pub fn log_view_draws(&self, font: FontId, line_height: f32) -> Vec<Draw2D> {
+        let Some(filter) = &self.log_view else {
+            return Vec::new();
+        };
+
+        let records = self.log.filtered(filter);
+        let shown = &records[records.len().saturating_sub(LOG_VIEW_LINES)..];
+        shown.iter().rev().enumerate().map(|(row, record)| {
+            let color = match record.level {
+                Level::ERROR => Color::RED,
+                Level::WARN => Color::YELLOW,
+                Level::INFO => Color::WHITE,
+                _ => Color::GRAY
+            };
+            let text = format!("{} {}: {}", record.level, record.target, record.to_string().lines().next().unwrap_or_default());
+            Draw2D::text(font, text, (0.0, -line_height * row as f32), color).anchored(Anchor::BottomRight).on_layer(1)
+        }).collect()
     }
 }
@@ -0,0 +1,182 @@
+use std::mem::size_of;
+use sdl2::pixels::Color;
+use crate::rendering::gpu;
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::state::in_game::GameSession;
+use crate::world::{block, world_to_chunk_pos, REACH};
+use crate::world::chunk::ChunkBlockData;
+use super::chunk::ChunkPanel;
+use super::{profiler, DebugOverlay};
+
+const GRAPH_HEIGHT: f32 = 80.0;
+const GRAPH_BAR_WIDTH: f32 = 2.0;
+//Pixels per millisecond, so a 60fps frame reaches a little under halfway up the graph
+const GRAPH_SCALE: f32 = 2.0;
+const TARGET_FRAME_MS: f64 = 1000.0 / 60.0;
+
+//Everything a panel can read from. Panels only show in game, so there is always a session
+pub struct PanelContext<'a> {
+    pub debugger: &'a DebugOverlay,
+    pub session: &'a GameSession
+}
+
+//One toggleable block of the debug overlay. Panels are registered on the DebugOverlay against an
+//F-key, and are asked for their lines every frame they are visible
+pub trait DebugPanel {
+    fn title(&self) -> &'static str;
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String>;
+
+    //Anything drawn outside the panel's column of text, like a graph. Panels can also submit world
+    //space shapes from here through debug::draw
+    fn extra_draws(&self, _ctx: &PanelContext) -> Vec<Draw2D> {
+        Vec::new()
+    }
+}
+
+pub fn register_builtin(debugger: &mut DebugOverlay) {
+    debugger.register_panel(1, Box::new(PerformancePanel));
+    debugger.register_panel(4, Box::new(CameraPanel));
+    debugger.register_panel(5, Box::new(WorldPanel));
+    debugger.register_panel(6, Box::new(MemoryPanel));
+    debugger.register_panel(7, Box::new(EventsPanel));
+    debugger.register_panel(10, Box::new(ChunkPanel));
+}
+
+//Frame time, draw calls and the profiler's per scope percentiles, with a frame time graph
+struct PerformancePanel;
+
+impl DebugPanel for PerformancePanel {
+    fn title(&self) -> &'static str {
+        "Performance"
+    }
+
+    fn lines(&self, _ctx: &PanelContext) -> Vec<String> {
+        let frame_ms = profiler::frame_times_ms().last().copied().unwrap_or(0.0);
+        let mut lines = vec![
+            format!("Frame: {:.2} ms ({:.0} fps)  Draws: {}", frame_ms, 1000.0 / frame_ms.max(0.001), gpu::draw_calls()),
+            format!("{:<14}{:>8}{:>8}{:>8}{:>8}", "ms", "avg", "p50", "p95", "p99")
+        ];
+        for stats in profiler::stats() {
+            lines.push(format!("{:<14}{:>8.2}{:>8.2}{:>8.2}{:>8.2}", stats.name, stats.average_ms, stats.p50_ms, stats.p95_ms, stats.p99_ms));
+        }
+        lines
+    }
+
+    //In the bottom left, one bar per frame, oldest on the left
+    fn extra_draws(&self, _ctx: &PanelContext) -> Vec<Draw2D> {
+        let width = profiler::FRAME_HISTORY as f32 * GRAPH_BAR_WIDTH;
+        let mut draws = vec![Draw2D::rect((0.0, 0.0), (width, GRAPH_HEIGHT), Color::RGBA(0, 0, 0, 160)).anchored(Anchor::BottomLeft)];
+        for (bar, frame_ms) in profiler::frame_times_ms().into_iter().enumerate() {
+            let color = if frame_ms <= TARGET_FRAME_MS {
+                Color::GREEN
+            } else if frame_ms <= TARGET_FRAME_MS * 2.0 {
+                Color::YELLOW
+            } else {
+                Color::RED
+            };
+            let height = (frame_ms as f32 * GRAPH_SCALE).min(GRAPH_HEIGHT);
+            draws.push(Draw2D::rect((bar as f32 * GRAPH_BAR_WIDTH, 0.0), (GRAPH_BAR_WIDTH, height), color).anchored(Anchor::BottomLeft).on_layer(1));
+        }
+        //Where a 60fps frame would reach
+        let target_y = -(TARGET_FRAME_MS as f32 * GRAPH_SCALE);
+        draws.push(Draw2D::rect((0.0, target_y), (width, 1.0), Color::WHITE).anchored(Anchor::BottomLeft).on_layer(2));
+        draws
+    }
+}
+
+struct CameraPanel;
+
+impl DebugPanel for CameraPanel {
+    fn title(&self) -> &'static str {
+        "Camera"
+    }
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String> {
+        let camera = &ctx.session.camera;
+        let world = &ctx.session.world;
+        let position = camera.position();
+        let chunk = world_to_chunk_pos(position);
+        vec![
+            format!("Pos: ({:.1}, {:.1}, {:.1})  Yaw: {:.1}  Pitch: {:.1}", position.x, position.y, position.z, camera.yaw().to_degrees(), camera.pitch().to_degrees()),
+            format!("Chunk: ({}, {}, {})  Loaded: {}  Distance: {}", chunk.0, chunk.1, chunk.2, world.chunks.len(), world.render_distance),
+            format!("View: {}", ctx.debugger.render_mode)
+        ]
+    }
+}
+
+struct WorldPanel;
+
+impl DebugPanel for WorldPanel {
+    fn title(&self) -> &'static str {
+        "World"
+    }
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String> {
+        let world = &ctx.session.world;
+        let camera = &ctx.session.camera;
+        let looking_at = match world.raycast(camera.position(), camera.forward(), REACH) {
+            Some(hit) => {
+                let name = block::definition(hit.block).map_or("unknown", |definition| definition.name);
+                format!("{} at ({}, {}, {})", name, hit.pos.0, hit.pos.1, hit.pos.2)
+            }
+            None => "nothing in reach".to_string()
+        };
+
+        vec![
+            format!("Seed: {}  Time of day: {:.2}", world.seed, world.clock.time_of_day()),
+            format!("Looking at: {}", looking_at)
+        ]
+    }
+}
+
+struct MemoryPanel;
+
+impl DebugPanel for MemoryPanel {
+    fn title(&self) -> &'static str {
+        "Memory"
+    }
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String> {
+        let gpu_resources: Vec<String> = gpu::live_counts().iter().map(|(kind, count)| format!("{} {}", kind, count)).collect();
+        let chunk_kib = (ctx.session.world.chunks.len() * size_of::<ChunkBlockData>()) as f64 / 1024.0;
+        let meshes = ctx.session.chunk_mesh_manager.borrow();
+
+        let mut lines = vec![
+            format!("GPU: {}", gpu_resources.join(" ")),
+            format!("Chunk blocks: {:.1} KiB", chunk_kib),
+            format!("Meshes: {} opaque, {} translucent", meshes.meshes().len(), meshes.translucent_meshes().len())
+        ];
+        if let Some(resident) = resident_bytes() {
+            lines.push(format!("Resident: {:.1} MiB", resident as f64 / (1024.0 * 1024.0)));
+        }
+        lines
+    }
+}
+
+//VmRSS is given in kB, so unlike statm's page counts it doesn't depend on the page size. Only
+//Linux has it, so elsewhere the line is left out
+fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().next()?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+struct EventsPanel;
+
+impl DebugPanel for EventsPanel {
+    fn title(&self) -> &'static str {
+        "Events"
+    }
+
+    fn lines(&self, ctx: &PanelContext) -> Vec<String> {
+        ctx.session.event_queue.stats().into_iter().map(|stats| format!(
+            "{}: {} listeners, {} pending, {} last dispatch",
+            stats.event_type,
+            stats.listeners,
+            stats.pending,
+            stats.last_dispatched
+        )).collect()
+    }
+}
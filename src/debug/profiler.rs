@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+//About four seconds at 60fps, which is what the overlay averages over and what a trace holds
+pub const FRAME_HISTORY: usize = 240;
+pub const TRACE_DIRECTORY: &str = "traces";
+
+thread_local! {
+    //Everything that is timed runs on the main thread, so scopes can record without being
+    //handed the profiler through every system they cover
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::new());
+}
+
+pub struct ScopeSample {
+    pub name: &'static str,
+    pub start: Instant,
+    pub duration: Duration
+}
+
+pub struct FrameProfile {
+    pub start: Instant,
+    pub duration: Duration,
+    //In the order they finished, so a nested scope comes before the one around it
+    pub scopes: Vec<ScopeSample>
+}
+
+//Per frame time spent in one scope, added up over every time it ran that frame. Frames where it
+//didn't run at all count as zero, so a rare spike shows in the high percentiles, not the average
+pub struct ScopeStats {
+    pub name: &'static str,
+    pub average_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64
+}
+
+struct Profiler {
+    epoch: Instant,
+    current: Option<FrameProfile>,
+    frames: VecDeque<FrameProfile>
+}
+
+impl Profiler {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            current: None,
+            frames: VecDeque::with_capacity(FRAME_HISTORY)
+        }
+    }
+}
+
+//Times everything until it is dropped, so bind it to a named variable rather than `_`
+pub struct ProfileScope {
+    name: &'static str,
+    start: Instant
+}
+
+pub fn scope(name: &'static str) -> ProfileScope {
+    ProfileScope { name, start: Instant::now() }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let sample = ScopeSample { name: self.name, start: self.start, duration: self.start.elapsed() };
+        PROFILER.with_borrow_mut(|profiler| {
+            //Anything timed outside a frame, like startup, isn't kept
+            if let Some(frame) = profiler.current.as_mut() {
+                frame.scopes.push(sample);
+            }
+        });
+    }
+}
+
+pub fn begin_frame() {
+    PROFILER.with_borrow_mut(|profiler| {
+        profiler.current = Some(FrameProfile { start: Instant::now(), duration: Duration::ZERO, scopes: Vec::new() });
+    });
+}
+
+pub fn end_frame() {
+    PROFILER.with_borrow_mut(|profiler| {
+        let Some(mut frame) = profiler.current.take() else {
+            return;
+        };
+        frame.duration = frame.start.elapsed();
+
+        if profiler.frames.len() == FRAME_HISTORY {
+            profiler.frames.pop_front();
+        }
+        profiler.frames.push_back(frame);
+    });
+}
+
+//Oldest first
+pub fn frame_times_ms() -> Vec<f64> {
+    PROFILER.with_borrow(|profiler| profiler.frames.iter().map(|frame| as_ms(frame.duration)).collect())
+}
+
+//The whole frame first, then each scope in the order it was first seen
+pub fn stats() -> Vec<ScopeStats> {
+    PROFILER.with_borrow(|profiler| {
+        let mut names: Vec<&'static str> = Vec::new();
+        for sample in profiler.frames.iter().flat_map(|frame| &frame.scopes) {
+            if !names.contains(&sample.name) {
+                names.push(sample.name);
+            }
+        }
+
+        let mut stats = vec![scope_stats("frame", profiler.frames.iter().map(|frame| as_ms(frame.duration)).collect())];
+        for name in names {
+            let per_frame = profiler.frames.iter()
+                .map(|frame| frame.scopes.iter().filter(|sample| sample.name == name).map(|sample| as_ms(sample.duration)).sum())
+                .collect();
+            stats.push(scope_stats(name, per_frame));
+        }
+        stats
+    })
+}
+
+fn scope_stats(name: &'static str, mut times: Vec<f64>) -> ScopeStats {
+    times.sort_by(f64::total_cmp);
+    let average_ms = if times.is_empty() { 0.0 } else { times.iter().sum::<f64>() / times.len() as f64 };
+    ScopeStats {
+        name,
+        average_ms,
+        p50_ms: percentile(&times, 50.0),
+        p95_ms: percentile(&times, 95.0),
+        p99_ms: percentile(&times, 99.0)
+    }
+}
+
+//Nearest rank on an already sorted slice
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+//Writes the frames in the history to the traces directory as Chrome trace event JSON, which opens
+//in chrome://tracing, Perfetto or Speedscope. Returns where it was written
+pub fn export_chrome_trace() -> io::Result<PathBuf> {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let path = Path::new(TRACE_DIRECTORY).join(format!("trace_{}.json", millis));
+    fs::create_dir_all(TRACE_DIRECTORY)?;
+    fs::write(&path, chrome_trace())?;
+    debug!("Wrote trace to {}.", path.display());
+    Ok(path)
+}
+
+//Every frame and scope as a complete ("X") event, with times in microseconds since the profiler
+//started. Names are all string literals from our own code, so they never need escaping
+fn chrome_trace() -> String {
+    PROFILER.with_borrow(|profiler| {
+        let micros = |instant: Instant| instant.duration_since(profiler.epoch).as_secs_f64() * 1_000_000.0;
+        let event = |name: &str, start: Instant, duration: Duration| format!(
+            "{{\"name\":\"{}\",\"cat\":\"kardashev\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":1}}",
+            name,
+            micros(start),
+            duration.as_secs_f64() * 1_000_000.0
+        );
+
+        let mut events = Vec::new();
+        for frame in &profiler.frames {
+            events.push(event("frame", frame.start, frame.duration));
+            for sample in &frame.scopes {
+                events.push(event(sample.name, sample.start, sample.duration));
+            }
+        }
+        format!("{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n", events.join(",\n"))
+    })
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+use sdl2::pixels::Color;
+use tracing::Level;
+use crate::logging::{LogBuffer, LogRecord};
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::rendering::text::FontId;
+use super::console::CONSOLE_TARGET;
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+//Toasts fade out over the end of their time rather than vanishing
+const TOAST_FADE: Duration = Duration::from_secs(1);
+const TOAST_LIMIT: usize = 4;
+const TOAST_MARGIN: f32 = 8.0;
+
+//Warnings and errors from the log, shown briefly in the top right whatever state the game is in.
+//The console's own output is left out, since it is already on screen where it was typed
+pub struct Toasts {
+    log: LogBuffer,
+    last_seen: u64,
+    toasts: Vec<LogRecord>
+}
+
+impl Toasts {
+    pub fn new(log: LogBuffer) -> Self {
+        Self {
+            log,
+            last_seen: 0,
+            toasts: Vec::new()
+        }
+    }
+
+    pub fn update(&mut self) {
+        let new_records = self.log.since(self.last_seen);
+        if let Some(newest) = new_records.last() {
+            self.last_seen = newest.id;
+        }
+
+        self.toasts.extend(new_records.into_iter().filter(|record| record.level <= Level::WARN && record.target != CONSOLE_TARGET));
+        self.toasts.retain(|toast| toast.time.elapsed() < TOAST_DURATION);
+        if self.toasts.len() > TOAST_LIMIT {
+            self.toasts.drain(..self.toasts.len() - TOAST_LIMIT);
+        }
+    }
+
+    //Newest at the top
+    pub fn draws(&self, font: FontId, line_height: f32) -> Vec<Draw2D> {
+        self.toasts.iter().rev().enumerate().map(|(row, toast)| {
+            let remaining = TOAST_DURATION.saturating_sub(toast.time.elapsed());
+            let alpha = (remaining.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0);
+            let color = match toast.level {
+                Level::ERROR => Color::RED,
+                _ => Color::YELLOW
+            };
+            let text = toast.to_string().lines().next().unwrap_or_default().to_string();
+            let position = (-TOAST_MARGIN, TOAST_MARGIN + line_height * row as f32);
+
+            Draw2D::text(font, text, position, Color::RGBA(color.r, color.g, color.b, (alpha * 255.0) as u8))
+                .anchored(Anchor::TopRight)
+                .on_layer(5)
+        }).collect()
+    }
+}
@@ -0,0 +1,39 @@
+use std::error::Error;
+use thiserror::Error;
+use tracing::subscriber::SetGlobalDefaultError;
+use crate::input::InputError;
+use crate::rendering::error::{FontError, RenderError};
+
+//Anything that can stop the game, from startup through to the main loop
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("Unable to set up logging")]
+    Logging(#[from] SetGlobalDefaultError),
+    //SDL only reports errors as strings, so keep which step it was on alongside the message
+    #[error("Unable to {step}: {message}")]
+    Sdl { step: &'static str, message: String },
+    #[error("Unable to start the renderer")]
+    Render(#[from] RenderError),
+    #[error("Unable to load fonts")]
+    Font(#[from] FontError),
+    #[error("Input handling failed")]
+    Input(#[from] InputError)
+}
+
+impl AppError {
+    pub fn sdl(step: &'static str) -> impl FnOnce(String) -> AppError {
+        move |message| AppError::Sdl { step, message }
+    }
+}
+
+//The error and everything that caused it, outermost first and one per line, so the root cause is
+//never hidden behind a vague top level message
+pub fn report(error: &dyn Error) -> String {
+    let mut report = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        report.push_str(&format!("\n  caused by: {}", cause));
+        source = cause.source();
+    }
+    report
+}
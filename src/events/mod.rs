@@ -1,20 +1,25 @@
 use crate::world::chunk::ChunkBlockData;
 use crate::world::ChunkPos;
+use crate::world::light::PaddedLight;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::collections::HashMap;
 use tracing::debug;
-use strum_macros::Display;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
 
 #[derive(Display)]
 pub enum Event {
-    ChunkLoaded(ChunkPos, ChunkBlockData),
+    ChunkLoaded(ChunkPos, ChunkBlockData, PaddedLight),
+    //The blocks or light of an already loaded chunk changed, so its mesh is stale
+    ChunkModified(ChunkPos, ChunkBlockData, PaddedLight),
     ChunkUnloaded(ChunkPos)
 }
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Display, EnumIter, Eq, Hash, PartialEq)]
 pub enum EventType {
     ChunkLoaded,
+    ChunkModified,
     ChunkUnloaded
 }
 
@@ -22,6 +27,7 @@ impl Event {
     pub fn event_type(&self) -> EventType {
         match self {
             Event::ChunkLoaded(..) => EventType::ChunkLoaded,
+            Event::ChunkModified(..) => EventType::ChunkModified,
             Event::ChunkUnloaded(..) => EventType::ChunkUnloaded,
         }
     }
@@ -34,14 +40,25 @@ pub trait EventHandler {
 
 pub struct EventQueue {
     events: Vec<Event>,
-    listeners: HashMap<EventType, Vec<Rc<RefCell<dyn EventHandler>>>>
+    listeners: HashMap<EventType, Vec<Rc<RefCell<dyn EventHandler>>>>,
+    //How many of each type the last dispatch_events call sent out
+    last_dispatched: HashMap<EventType, usize>
+}
+
+//A snapshot of one event type, for the debug overlay
+pub struct EventTypeStats {
+    pub event_type: EventType,
+    pub listeners: usize,
+    pub pending: usize,
+    pub last_dispatched: usize
 }
 
 impl EventQueue {
     pub fn new() -> Self {
         Self {
             events: Vec::new(),
-            listeners: HashMap::new()
+            listeners: HashMap::new(),
+            last_dispatched: HashMap::new()
         }
     }
 
@@ -68,7 +85,9 @@ impl EventQueue {
 
     pub fn dispatch_events(&mut self) {
         debug!("Dispatching {} events...", self.events.len());
+        self.last_dispatched.clear();
         for event in self.events.drain(..) {
+            *self.last_dispatched.entry(event.event_type()).or_default() += 1;
             if let Some(listeners) = self.listeners.get(&event.event_type()) {
             debug!("Dispatching {} event to {} listeners...", event, listeners.len());
                 for listener in listeners {
@@ -77,4 +96,24 @@ impl EventQueue {
             }
         }
     }
+
+    //Pushed since the last dispatch, oldest first
+    pub fn pending_events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn stats(&self) -> Vec<EventTypeStats> {
+        EventType::iter().map(|event_type| EventTypeStats {
+            event_type,
+            listeners: self.listeners.get(&event_type).map_or(0, Vec::len),
+            pending: self.events.iter().filter(|event| event.event_type() == event_type).count(),
+            last_dispatched: self.last_dispatched.get(&event_type).copied().unwrap_or(0)
+        }).collect()
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
 }
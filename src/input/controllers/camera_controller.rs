@@ -3,6 +3,7 @@ use glam::Vec3;
 use crate::input::controllers::MouseMotion;
 use sdl2::keyboard::Keycode;
 use crate::input::{Controller, InputAction};
+use crate::settings::Settings;
 use super::{Input, KeyMap};
 
 pub struct CameraController {
@@ -11,10 +12,10 @@ pub struct CameraController {
 }
 
 impl<'a> CameraController {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
         Self {
-            movement_speed: 0.1,
-            look_sensitivity: 0.01
+            movement_speed: settings.movement_speed,
+            look_sensitivity: settings.look_sensitivity
         }
     }
 }
@@ -26,7 +27,9 @@ impl Controller for CameraController {
         keymap.insert(Input::KeyHeld(Keycode::A), InputAction::MoveCamera(Vec3::new(-1.0, 0.0, 0.0) * self.movement_speed));
         keymap.insert(Input::KeyHeld(Keycode::S), InputAction::MoveCamera(Vec3::new(0.0, 0.0, 1.0) * self.movement_speed));
         keymap.insert(Input::KeyHeld(Keycode::D), InputAction::MoveCamera(Vec3::new(1.0, 0.0, 0.0) * self.movement_speed));
-        keymap.insert(Input::KeyHeld(Keycode::ESCAPE), InputAction::Quit);
+        keymap.insert(Input::KeyPressed(Keycode::ESCAPE), InputAction::Pause);
+        //Alt-tabbing away mid game shouldn't leave the world running with the mouse let go
+        keymap.insert(Input::FocusLost, InputAction::Pause);
         keymap
     }
 
@@ -1,4 +1,4 @@
-use crate::input::{Controller, controllers::KeyMap};
+use crate::input::{Controller, InputAction, controllers::{KeyMap, MouseMotion}};
 use std::collections::{HashMap, HashSet};
 use tracing::debug;
 
@@ -22,6 +22,12 @@ impl CompositeController {
     }
 }
 
+impl Default for CompositeController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Controller for CompositeController {
     fn keymap(&self) -> KeyMap {
        let mut map = HashMap::new();
@@ -40,4 +46,17 @@ impl Controller for CompositeController {
 
        map
     }
+
+    //Mouse motion and text have no keymap entry to claim, so the topmost layer that does anything
+    //with them takes them
+    fn handle_mouse(&self, mouse_motion: MouseMotion) -> Option<InputAction> {
+        self.layers.iter().rev().find_map(|layer| layer.handle_mouse(mouse_motion))
+    }
+
+    fn handle_text(&self, text: &str) -> Vec<InputAction> {
+        self.layers.iter().rev()
+            .map(|layer| layer.handle_text(text))
+            .find(|actions| !actions.is_empty())
+            .unwrap_or_default()
+    }
 }
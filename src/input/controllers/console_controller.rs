@@ -0,0 +1,37 @@
+use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
+
+use crate::input::InputAction;
+use crate::input::{Controller, controllers::KeyMap};
+
+use super::Input;
+
+//Typing the key that opens and closes the console also sends it as text, which shouldn't end up in
+//the input line
+const TOGGLE_CHARACTER: char = '`';
+
+//Takes over the keyboard while the console is open, so typing doesn't also move the camera
+pub struct ConsoleController {}
+
+impl Controller for ConsoleController {
+    fn keymap(&self) -> KeyMap {
+        let mut map = HashMap::new();
+        map.insert(Input::KeyPressed(Keycode::BACKQUOTE), InputAction::ToggleConsole);
+        map.insert(Input::KeyPressed(Keycode::ESCAPE), InputAction::ToggleConsole);
+        map.insert(Input::KeyPressed(Keycode::RETURN), InputAction::ConsoleSubmit);
+        map.insert(Input::KeyPressed(Keycode::BACKSPACE), InputAction::ConsoleErase);
+        map.insert(Input::KeyPressed(Keycode::TAB), InputAction::ConsoleComplete);
+        map.insert(Input::KeyPressed(Keycode::UP), InputAction::ConsoleHistory(-1));
+        map.insert(Input::KeyPressed(Keycode::DOWN), InputAction::ConsoleHistory(1));
+        map.insert(Input::KeyPressed(Keycode::PAGEUP), InputAction::ConsoleScroll(8));
+        map.insert(Input::KeyPressed(Keycode::PAGEDOWN), InputAction::ConsoleScroll(-8));
+        map
+    }
+
+    fn handle_text(&self, text: &str) -> Vec<InputAction> {
+        text.chars()
+            .filter(|character| *character != TOGGLE_CHARACTER)
+            .map(InputAction::ConsoleChar)
+            .collect()
+    }
+}
@@ -6,14 +6,32 @@ use crate::input::{Controller, controllers::KeyMap};
 
 use super::Input;
 
+//F-keys that toggle whichever debug panel is registered with their number. F2 takes screenshots
+//and F3 cycles the render mode, so they are left out
+const PANEL_KEYS: [(Keycode, i32); 8] = [
+    (Keycode::F1, 1),
+    (Keycode::F4, 4),
+    (Keycode::F5, 5),
+    (Keycode::F6, 6),
+    (Keycode::F7, 7),
+    (Keycode::F10, 10),
+    (Keycode::F11, 11),
+    (Keycode::F12, 12),
+];
+
 pub struct DebugOverlayController {}
 
 impl Controller for DebugOverlayController {
 
     fn keymap(&self) -> KeyMap {
         let mut map = HashMap::new();
-        map.insert(Input::KeyPressed(Keycode::F1), InputAction::ToggleDebugModule(1));
-        map.insert(Input::KeyHeld(Keycode::S), InputAction::ToggleDebugModule(1));
+        for (keycode, number) in PANEL_KEYS {
+            map.insert(Input::KeyPressed(keycode), InputAction::ToggleDebugModule(number));
+        }
+        map.insert(Input::KeyPressed(Keycode::F3), InputAction::CycleRenderMode);
+        map.insert(Input::KeyPressed(Keycode::F8), InputAction::ToggleLogView);
+        map.insert(Input::KeyPressed(Keycode::F9), InputAction::ExportTrace);
+        map.insert(Input::KeyPressed(Keycode::BACKQUOTE), InputAction::ToggleConsole);
         map
     }
 }
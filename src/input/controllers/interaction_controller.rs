@@ -0,0 +1,21 @@
+use sdl2::mouse::MouseButton;
+use std::collections::HashMap;
+
+use crate::input::InputAction;
+use crate::input::{Controller, controllers::KeyMap};
+
+use super::Input;
+
+//Breaking and placing blocks, and picking which one to place
+pub struct InteractionController {}
+
+impl Controller for InteractionController {
+    fn keymap(&self) -> KeyMap {
+        let mut map = HashMap::new();
+        map.insert(Input::ButtonPressed(MouseButton::Left), InputAction::BreakBlock);
+        map.insert(Input::ButtonPressed(MouseButton::Right), InputAction::PlaceBlock);
+        map.insert(Input::WheelUp, InputAction::HotbarScroll(-1));
+        map.insert(Input::WheelDown, InputAction::HotbarScroll(1));
+        map
+    }
+}
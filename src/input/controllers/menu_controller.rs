@@ -0,0 +1,28 @@
+use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
+
+use crate::input::InputAction;
+use crate::input::{Controller, controllers::KeyMap};
+
+use super::Input;
+
+pub struct MenuController {}
+
+impl Controller for MenuController {
+    fn keymap(&self) -> KeyMap {
+        let mut map = HashMap::new();
+        map.insert(Input::KeyPressed(Keycode::UP), InputAction::MenuNavigate(-1));
+        map.insert(Input::KeyPressed(Keycode::DOWN), InputAction::MenuNavigate(1));
+        map.insert(Input::KeyPressed(Keycode::LEFT), InputAction::MenuAdjust(-1));
+        map.insert(Input::KeyPressed(Keycode::RIGHT), InputAction::MenuAdjust(1));
+        map.insert(Input::KeyPressed(Keycode::RETURN), InputAction::MenuSelect);
+        map.insert(Input::KeyPressed(Keycode::ESCAPE), InputAction::MenuBack);
+        map.insert(Input::KeyPressed(Keycode::BACKSPACE), InputAction::MenuErase);
+        map
+    }
+
+    //Every menu gets whatever is typed, and picks out the characters its text boxes accept
+    fn handle_text(&self, text: &str) -> Vec<InputAction> {
+        text.chars().map(InputAction::MenuChar).collect()
+    }
+}
@@ -1,11 +1,15 @@
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use crate::input::FrameInput;
 use std::collections::{HashMap, HashSet};
 use super::InputAction;
 
 pub mod composite_controller;
 pub mod camera_controller;
+pub mod console_controller;
 pub mod debug_overlay_controller;
+pub mod interaction_controller;
+pub mod menu_controller;
 
 pub trait Controller {
     fn keymap(&self) -> KeyMap;
@@ -16,7 +20,8 @@ pub trait Controller {
             match input {
                 Input::KeyPressed(keycode) => keys.insert(*keycode),
                 Input::KeyHeld(keycode) => keys.insert(*keycode),
-                Input::KeyReleased(keycode) => keys.insert(*keycode)
+                Input::KeyReleased(keycode) => keys.insert(*keycode),
+                _ => false
             };
         }
         keys
@@ -28,6 +33,9 @@ pub trait Controller {
         if let Some(mouse_action) = mouse_action {
             actions.push(mouse_action);
         }
+        if !input.text_input.is_empty() {
+            actions.extend(self.handle_text(&input.text_input));
+        }
         actions
     }
 
@@ -44,15 +52,28 @@ pub trait Controller {
     fn handle_mouse(&self, mouse_motion: MouseMotion) -> Option<InputAction> {
         None
     }
+
+    //Typed text, already laid out by the keyboard layout and shift, for anything with a text box
+    fn handle_text(&self, _text: &str) -> Vec<InputAction> {
+        Vec::new()
+    }
 }
 
 pub type MouseMotion = (i32, i32);
 
-#[derive(Eq, Hash, PartialEq, Clone)]
+#[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum Input {
     KeyPressed(Keycode),
     KeyHeld(Keycode),
     KeyReleased(Keycode),
+    ButtonPressed(MouseButton),
+    ButtonHeld(MouseButton),
+    ButtonReleased(MouseButton),
+    //One per frame the wheel moved in that direction, however far it went
+    WheelUp,
+    WheelDown,
+    FocusGained,
+    FocusLost
 }
 
 pub type KeyMap = HashMap<Input, InputAction>;
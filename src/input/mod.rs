@@ -1,23 +1,57 @@
 pub mod controllers;
+pub mod recording;
+pub mod source;
 
 use crate::input::controllers::{Input, Controller};
 use controllers::MouseMotion;
+use recording::{InputRecorder, RecordingError};
+use source::{InputSource, RawInput};
 use tracing::debug;
 use std::collections::HashSet;
 use glam::Vec3;
-use sdl2::{event::Event, keyboard::Keycode, EventPump};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use thiserror::Error;
+
+const SCREENSHOT_KEY: Keycode = Keycode::F2;
+
+#[derive(Debug, Error)]
+pub enum InputError {
+    #[error("Input arrived with no active controller to handle it")]
+    NoController,
+    #[error("Input recording failed")]
+    Recording(#[from] RecordingError)
+}
 
 //TODO - Create an input buffer for the InputDispatcher
 pub struct InputDispatcher<'a> {
-    event_pump: EventPump,
+    source: Box<dyn InputSource + 'a>,
     active_controller: Option<Box<dyn Controller + 'a>>,
     keys_held: HashSet<Keycode>,
-    mouse_motion: Option<(i32, i32)>
+    buttons_held: HashSet<MouseButton>,
+    mouse_motion: Option<(i32, i32)>,
+    //Counts every poll, so recorded frames can be put back on the frame they happened
+    tick: u64,
+    recorder: Option<InputRecorder>
 }
 
 pub struct FrameInput {
     pub keys_input: HashSet<Input>,
-    pub mouse_input: Option<MouseMotion>
+    pub mouse_input: Option<MouseMotion>,
+    //Everything typed this frame, in order
+    pub text_input: String,
+    pub quit_requested: bool
+}
+
+impl FrameInput {
+    pub fn empty() -> Self {
+        Self {
+            keys_input: HashSet::new(),
+            mouse_input: None,
+            text_input: String::new(),
+            quit_requested: false
+        }
+    }
 }
 
 //TODO - this is temp code for emitting actions to stop CameraController possessing a mutable
@@ -26,68 +60,238 @@ pub struct FrameInput {
 pub enum InputAction {
     MoveCamera(Vec3),
     LookDelta((f32, f32)),
+    BreakBlock,
+    PlaceBlock,
+    HotbarScroll(i32),
     ToggleDebugModule(i32),
+    CycleRenderMode,
+    ExportTrace,
+    ToggleLogView,
+    ToggleConsole,
+    ConsoleChar(char),
+    ConsoleErase,
+    ConsoleSubmit,
+    ConsoleComplete,
+    ConsoleHistory(i32),
+    ConsoleScroll(i32),
+    Screenshot,
+    Pause,
+    MenuNavigate(i32),
+    MenuAdjust(i32),
+    MenuSelect,
+    MenuBack,
+    MenuChar(char),
+    MenuErase,
     Quit
 }
 
 impl<'a> InputDispatcher<'a> {
-    pub fn new(event_pump: EventPump) -> InputDispatcher<'a> {
+    pub fn new(source: Box<dyn InputSource + 'a>) -> InputDispatcher<'a> {
         let input_handler = InputDispatcher {
-            event_pump,
+            source,
             active_controller: None,
             keys_held: HashSet::new(),
-            mouse_motion: None
+            buttons_held: HashSet::new(),
+            mouse_motion: None,
+            tick: 0,
+            recorder: None
         };
         
 
         return input_handler;
     }
 
-    pub fn set_controller<C: Controller + 'a>(&mut self, controller: C) {
+    pub fn set_controller(&mut self, controller: Box<dyn Controller + 'a>) {
         debug!("Active controller changing...");
-        self.active_controller = Some(Box::new(controller)); 
+        self.active_controller = Some(controller); 
+    }
+
+    pub fn record(&mut self, recorder: InputRecorder) {
+        self.recorder = Some(recorder);
     }
 
-    pub fn poll_events(&mut self) -> Result<FrameInput, String> {
+    pub fn poll_events(&mut self) -> Result<FrameInput, InputError> {
+        let tick = self.tick;
+        self.tick += 1;
+
+        let input = self.track_keys();
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(tick, &input)?;
+        }
+        Ok(input)
+    }
+
+    //Turns the source's key and button downs and ups into presses, holds and releases
+    fn track_keys(&mut self) -> FrameInput {
         debug!("Polling for input events...");
         let mut keys_input = HashSet::new();
+        let mut text_input = String::new();
+        let mut quit_requested = false;
         self.mouse_motion = None;
 
-        for event in self.event_pump.poll_iter() {
+        for event in self.source.poll() {
             match event {
-                Event::KeyDown { keycode: Some(k), repeat: false, .. } => {
+                RawInput::Quit => {
+                    quit_requested = true;
+                }
+                RawInput::KeyDown(k) => {
                     if !self.keys_held.contains(&k) {
                         keys_input.insert(Input::KeyPressed(k));
                     }
                     self.keys_held.insert(k);
                 }
-                Event::KeyUp { keycode: Some(k), .. } => {
+                RawInput::KeyUp(k) => {
                     self.keys_held.remove(&k);
                     keys_input.insert(Input::KeyReleased(k));
                 }
-                Event::MouseMotion { xrel, yrel, .. } => {
-                    self.mouse_motion = Some((xrel, yrel));
+                RawInput::ButtonDown(button) => {
+                    if self.buttons_held.insert(button) {
+                        keys_input.insert(Input::ButtonPressed(button));
+                    }
+                }
+                RawInput::ButtonUp(button) => {
+                    self.buttons_held.remove(&button);
+                    keys_input.insert(Input::ButtonReleased(button));
+                }
+                RawInput::MouseMotion(motion) => {
+                    self.mouse_motion = Some(motion);
+                }
+                RawInput::Wheel(steps) if steps > 0 => {
+                    keys_input.insert(Input::WheelUp);
+                }
+                RawInput::Wheel(steps) if steps < 0 => {
+                    keys_input.insert(Input::WheelDown);
+                }
+                RawInput::Wheel(_) => {}
+                RawInput::Text(text) => text_input.push_str(&text),
+                RawInput::FocusGained => {
+                    keys_input.insert(Input::FocusGained);
+                }
+                RawInput::FocusLost => {
+                    //Anything let go of while the window is in the background never sends its up,
+                    //so release everything rather than leave it stuck down
+                    keys_input.insert(Input::FocusLost);
+                    keys_input.extend(self.keys_held.drain().map(Input::KeyReleased));
+                    keys_input.extend(self.buttons_held.drain().map(Input::ButtonReleased));
                 }
-                _ => {}
             }
         }
 
         for key in self.keys_held.clone() {
             keys_input.insert(Input::KeyHeld(key));
         }
+        for button in self.buttons_held.clone() {
+            keys_input.insert(Input::ButtonHeld(button));
+        }
 
-        Ok(FrameInput {
+        FrameInput {
             keys_input,
             mouse_input: self.mouse_motion,
-        })
+            text_input,
+            quit_requested
+        }
     }
 
-    pub fn update(&mut self) -> Result<Vec<InputAction>, String> {
+    pub fn update(&mut self) -> Result<Vec<InputAction>, InputError> {
         let input = self.poll_events()?;
-        return Ok(self.active_controller
+        let quit_requested = input.quit_requested;
+        let screenshot_requested = input.keys_input.contains(&Input::KeyPressed(SCREENSHOT_KEY));
+        let mut actions = self.active_controller
             .as_mut()
-            .expect("No active controller!")
-            .handle_input(input)
-        );
+            .ok_or(InputError::NoController)?
+            .handle_input(input);
+
+        //Closing the window should always quit, whichever controller is active
+        if quit_requested {
+            actions.push(InputAction::Quit);
+        }
+        //Likewise screenshots can be taken anywhere, menus included
+        if screenshot_requested {
+            actions.push(InputAction::Screenshot);
+        }
+
+        Ok(actions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use source::ScriptedInputSource;
+
+    fn dispatcher(frames: Vec<Vec<RawInput>>) -> InputDispatcher<'static> {
+        InputDispatcher::new(Box::new(ScriptedInputSource::with_frames(frames)))
+    }
+
+    fn keys(dispatcher: &mut InputDispatcher) -> HashSet<Input> {
+        dispatcher.poll_events().unwrap().keys_input
+    }
+
+    #[test]
+    fn key_down_presses_on_its_frame_then_holds() {
+        let mut dispatcher = dispatcher(vec![vec![RawInput::KeyDown(Keycode::W)], vec![]]);
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::KeyPressed(Keycode::W), Input::KeyHeld(Keycode::W)]));
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::KeyHeld(Keycode::W)]));
+    }
+
+    #[test]
+    fn repeated_key_down_does_not_press_again() {
+        let mut dispatcher = dispatcher(vec![vec![RawInput::KeyDown(Keycode::W)], vec![RawInput::KeyDown(Keycode::W)]]);
+        keys(&mut dispatcher);
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::KeyHeld(Keycode::W)]));
+    }
+
+    #[test]
+    fn key_up_releases_and_stops_holding() {
+        let mut dispatcher = dispatcher(vec![vec![RawInput::KeyDown(Keycode::W)], vec![RawInput::KeyUp(Keycode::W)], vec![]]);
+        keys(&mut dispatcher);
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::KeyReleased(Keycode::W)]));
+        assert!(keys(&mut dispatcher).is_empty());
+    }
+
+    #[test]
+    fn focus_lost_releases_everything_held() {
+        let mut dispatcher = dispatcher(vec![
+            vec![RawInput::KeyDown(Keycode::W), RawInput::KeyDown(Keycode::A), RawInput::ButtonDown(MouseButton::Left)],
+            vec![RawInput::FocusLost],
+            vec![]
+        ]);
+        keys(&mut dispatcher);
+        assert_eq!(keys(&mut dispatcher), HashSet::from([
+            Input::FocusLost,
+            Input::KeyReleased(Keycode::W),
+            Input::KeyReleased(Keycode::A),
+            Input::ButtonReleased(MouseButton::Left)
+        ]));
+        assert!(keys(&mut dispatcher).is_empty());
+    }
+
+    #[test]
+    fn buttons_and_wheel_map_to_their_inputs() {
+        let mut dispatcher = dispatcher(vec![
+            vec![RawInput::ButtonDown(MouseButton::Right), RawInput::Wheel(2)],
+            vec![RawInput::Wheel(-1)],
+            vec![RawInput::ButtonUp(MouseButton::Right), RawInput::Wheel(0)]
+        ]);
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::ButtonPressed(MouseButton::Right), Input::ButtonHeld(MouseButton::Right), Input::WheelUp]));
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::ButtonHeld(MouseButton::Right), Input::WheelDown]));
+        assert_eq!(keys(&mut dispatcher), HashSet::from([Input::ButtonReleased(MouseButton::Right)]));
+    }
+
+    #[test]
+    fn motion_text_and_quit_pass_through() {
+        let mut dispatcher = dispatcher(vec![
+            vec![RawInput::MouseMotion((4, -2)), RawInput::Text("a".to_string()), RawInput::Text("b".to_string()), RawInput::Quit],
+            vec![]
+        ]);
+        let input = dispatcher.poll_events().unwrap();
+        assert_eq!(input.mouse_input, Some((4, -2)));
+        assert_eq!(input.text_input, "ab");
+        assert!(input.quit_requested);
+
+        let input = dispatcher.poll_events().unwrap();
+        assert_eq!(input.mouse_input, None);
+        assert!(input.text_input.is_empty());
+        assert!(!input.quit_requested);
     }
 }
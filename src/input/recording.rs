@@ -0,0 +1,410 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use thiserror::Error;
+use tracing::{debug, info};
+use super::FrameInput;
+use super::controllers::Input;
+use super::source::{InputSource, RawInput};
+
+//Recorded and replayed sessions step the world by this much every frame, whatever the real frame
+//time was, so a replay lands in exactly the same place
+pub const FIXED_TIMESTEP: Duration = Duration::from_micros(16_667);
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("Unable to create input recording {}", .path.display())]
+    Create { path: PathBuf, #[source] source: io::Error },
+    #[error("Unable to write input recording {}", .path.display())]
+    Write { path: PathBuf, #[source] source: io::Error },
+    #[error("Unable to read input recording {}", .path.display())]
+    Read { path: PathBuf, #[source] source: io::Error },
+    #[error("Input recording {} has no valid seed", .path.display())]
+    MissingSeed { path: PathBuf },
+    #[error("Input recording {} has an invalid frame on line {line}", .path.display())]
+    InvalidFrame { path: PathBuf, line: usize }
+}
+
+//Writes every frame with any input in it, as it happens, so a recording survives the game crashing.
+//The file is a `seed=` line followed by one line per frame:
+//`<tick> p<keycode> h<keycode> r<keycode> bp<button> bh<button> br<button> wu wd fg fl m<x>,<y> t<text> quit`,
+//with each part only there if it happened
+pub struct InputRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>
+}
+
+impl InputRecorder {
+    pub fn create<T: AsRef<Path>>(path: T, seed: u32) -> Result<Self, RecordingError> {
+        let path = path.as_ref().to_path_buf();
+        debug!("Recording input to {}...", path.display());
+
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|source| RecordingError::Create { path: path.clone(), source })?;
+        }
+        let file = File::create(&path).map_err(|source| RecordingError::Create { path: path.clone(), source })?;
+
+        let mut recorder = Self { path, writer: BufWriter::new(file) };
+        recorder.write_line(&format!("seed={}", seed))?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, tick: u64, input: &FrameInput) -> Result<(), RecordingError> {
+        if input.keys_input.is_empty() && input.mouse_input.is_none() && input.text_input.is_empty() && !input.quit_requested {
+            return Ok(());
+        }
+        self.write_line(&format_frame(tick, input))
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), RecordingError> {
+        writeln!(self.writer, "{}", line)
+            .and_then(|()| self.writer.flush())
+            .map_err(|source| RecordingError::Write { path: self.path.clone(), source })
+    }
+}
+
+//A recording read back, to be played through a ReplayInputSource
+pub struct InputReplay {
+    pub seed: u32,
+    frames: VecDeque<(u64, FrameInput)>
+}
+
+impl InputReplay {
+    pub fn read<T: AsRef<Path>>(path: T) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        debug!("Reading input recording from {}...", path.display());
+
+        let contents = fs::read_to_string(path).map_err(|source| RecordingError::Read { path: path.to_path_buf(), source })?;
+        let mut lines = contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+
+        let seed = lines.next()
+            .and_then(|(_, line)| line.trim().strip_prefix("seed="))
+            .and_then(|seed| seed.parse::<u32>().ok())
+            .ok_or_else(|| RecordingError::MissingSeed { path: path.to_path_buf() })?;
+
+        let mut frames = VecDeque::new();
+        for (index, line) in lines {
+            let frame = parse_frame(line).ok_or_else(|| RecordingError::InvalidFrame { path: path.to_path_buf(), line: index + 1 })?;
+            frames.push_back(frame);
+        }
+        debug!("Read {} recorded frames.", frames.len());
+
+        Ok(Self { seed, frames })
+    }
+
+    fn next_frame(&mut self, tick: u64) -> Option<FrameInput> {
+        match self.frames.front() {
+            Some((frame_tick, _)) if *frame_tick <= tick => self.frames.pop_front().map(|(_, input)| input),
+            _ => None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+//Plays a recording back in place of another source until it runs out, then hands over to it. The
+//other source is still polled during the replay so the window stays responsive, and closing it
+//still quits
+pub struct ReplayInputSource<'a> {
+    replay: Option<InputReplay>,
+    fallback: Box<dyn InputSource + 'a>,
+    tick: u64,
+    keys_down: HashSet<Keycode>,
+    buttons_down: HashSet<MouseButton>
+}
+
+impl<'a> ReplayInputSource<'a> {
+    pub fn new(replay: InputReplay, fallback: Box<dyn InputSource + 'a>) -> Self {
+        Self {
+            replay: Some(replay),
+            fallback,
+            tick: 0,
+            keys_down: HashSet::new(),
+            buttons_down: HashSet::new()
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+}
+
+impl InputSource for ReplayInputSource<'_> {
+    fn poll(&mut self) -> Vec<RawInput> {
+        let tick = self.tick;
+        self.tick += 1;
+
+        let live = self.fallback.poll();
+        let Some(replay) = self.replay.as_mut() else {
+            return live;
+        };
+
+        let mut raw = replay.next_frame(tick).map(|input| frame_to_raw(&input)).unwrap_or_default();
+        for input in &raw {
+            match input {
+                RawInput::KeyDown(keycode) => { self.keys_down.insert(*keycode); }
+                RawInput::KeyUp(keycode) => { self.keys_down.remove(keycode); }
+                RawInput::ButtonDown(button) => { self.buttons_down.insert(*button); }
+                RawInput::ButtonUp(button) => { self.buttons_down.remove(button); }
+                _ => {}
+            }
+        }
+        if live.contains(&RawInput::Quit) {
+            raw.push(RawInput::Quit);
+        }
+
+        if replay.is_finished() {
+            info!("Replay finished on tick {}, handing input back to the keyboard and mouse.", tick);
+            self.replay = None;
+            //Otherwise anything held at the end of the recording would stay held forever
+            raw.extend(self.keys_down.drain().map(RawInput::KeyUp));
+            raw.extend(self.buttons_down.drain().map(RawInput::ButtonUp));
+        }
+        raw
+    }
+}
+
+//Held keys and buttons come back from the dispatcher's own tracking, so only presses and releases
+//are needed
+fn frame_to_raw(input: &FrameInput) -> Vec<RawInput> {
+    let mut raw = Vec::new();
+    let keys: Vec<(Keycode, bool)> = input.keys_input.iter().filter_map(|key| match key {
+        Input::KeyPressed(keycode) => Some((*keycode, true)),
+        Input::KeyReleased(keycode) => Some((*keycode, false)),
+        _ => None
+    }).collect();
+    let buttons: Vec<(MouseButton, bool)> = input.keys_input.iter().filter_map(|key| match key {
+        Input::ButtonPressed(button) => Some((*button, true)),
+        Input::ButtonReleased(button) => Some((*button, false)),
+        _ => None
+    }).collect();
+    push_presses(&mut raw, &keys, |keycode| input.keys_input.contains(&Input::KeyHeld(keycode)), RawInput::KeyDown, RawInput::KeyUp);
+    push_presses(&mut raw, &buttons, |button| input.keys_input.contains(&Input::ButtonHeld(button)), RawInput::ButtonDown, RawInput::ButtonUp);
+
+    if let Some(motion) = input.mouse_input {
+        raw.push(RawInput::MouseMotion(motion));
+    }
+    if input.keys_input.contains(&Input::WheelUp) {
+        raw.push(RawInput::Wheel(1));
+    }
+    if input.keys_input.contains(&Input::WheelDown) {
+        raw.push(RawInput::Wheel(-1));
+    }
+    if !input.text_input.is_empty() {
+        raw.push(RawInput::Text(input.text_input.clone()));
+    }
+    if input.keys_input.contains(&Input::FocusGained) {
+        raw.push(RawInput::FocusGained);
+    }
+    if input.keys_input.contains(&Input::FocusLost) {
+        raw.push(RawInput::FocusLost);
+    }
+    if input.quit_requested {
+        raw.push(RawInput::Quit);
+    }
+    raw
+}
+
+//Something both released and pressed in one frame ends up held only if it was released first, so
+//that decides which order the downs and ups go in
+fn push_presses<T: Copy>(raw: &mut Vec<RawInput>, presses: &[(T, bool)], held: impl Fn(T) -> bool, down: fn(T) -> RawInput, up: fn(T) -> RawInput) {
+    let downs = presses.iter().filter(|(_, pressed)| *pressed).map(|(input, _)| *input);
+    let ups = presses.iter().filter(|(_, pressed)| !*pressed).map(|(input, _)| *input);
+
+    raw.extend(downs.clone().filter(|input| !held(*input)).map(down));
+    raw.extend(ups.map(up));
+    raw.extend(downs.filter(|input| held(*input)).map(down));
+}
+
+fn format_frame(tick: u64, input: &FrameInput) -> String {
+    //Sorted, so the same input always writes the same line
+    let mut parts: Vec<String> = input.keys_input.iter().map(|input| match input {
+        Input::KeyPressed(keycode) => format!("p{}", keycode.into_i32()),
+        Input::KeyHeld(keycode) => format!("h{}", keycode.into_i32()),
+        Input::KeyReleased(keycode) => format!("r{}", keycode.into_i32()),
+        Input::ButtonPressed(button) => format!("bp{}", *button as u8),
+        Input::ButtonHeld(button) => format!("bh{}", *button as u8),
+        Input::ButtonReleased(button) => format!("br{}", *button as u8),
+        Input::WheelUp => "wu".to_string(),
+        Input::WheelDown => "wd".to_string(),
+        Input::FocusGained => "fg".to_string(),
+        Input::FocusLost => "fl".to_string()
+    }).collect();
+    parts.sort();
+
+    let mut line = tick.to_string();
+    for part in parts {
+        line.push(' ');
+        line.push_str(&part);
+    }
+    if let Some((x, y)) = input.mouse_input {
+        line.push_str(&format!(" m{},{}", x, y));
+    }
+    //As character codes, so spaces and anything else typed can't break up the line
+    if !input.text_input.is_empty() {
+        let codes: Vec<String> = input.text_input.chars().map(|character| (character as u32).to_string()).collect();
+        line.push_str(&format!(" t{}", codes.join(",")));
+    }
+    if input.quit_requested {
+        line.push_str(" quit");
+    }
+    line
+}
+
+fn parse_frame(line: &str) -> Option<(u64, FrameInput)> {
+    let mut parts = line.split_whitespace();
+    let tick = parts.next()?.parse::<u64>().ok()?;
+    let mut input = FrameInput::empty();
+
+    for part in parts {
+        let keycode = |value: &str| value.parse::<i32>().ok().and_then(Keycode::from_i32);
+        let button = |value: &str| value.parse::<u8>().ok().map(MouseButton::from_ll);
+
+        match part {
+            "quit" => input.quit_requested = true,
+            "wu" => { input.keys_input.insert(Input::WheelUp); }
+            "wd" => { input.keys_input.insert(Input::WheelDown); }
+            "fg" => { input.keys_input.insert(Input::FocusGained); }
+            "fl" => { input.keys_input.insert(Input::FocusLost); }
+            _ => {
+                //Longer prefixes first, so the button ones aren't read as keys
+                if let Some(value) = part.strip_prefix("bp") {
+                    input.keys_input.insert(Input::ButtonPressed(button(value)?));
+                } else if let Some(value) = part.strip_prefix("bh") {
+                    input.keys_input.insert(Input::ButtonHeld(button(value)?));
+                } else if let Some(value) = part.strip_prefix("br") {
+                    input.keys_input.insert(Input::ButtonReleased(button(value)?));
+                } else if let Some(value) = part.strip_prefix('p') {
+                    input.keys_input.insert(Input::KeyPressed(keycode(value)?));
+                } else if let Some(value) = part.strip_prefix('h') {
+                    input.keys_input.insert(Input::KeyHeld(keycode(value)?));
+                } else if let Some(value) = part.strip_prefix('r') {
+                    input.keys_input.insert(Input::KeyReleased(keycode(value)?));
+                } else if let Some(value) = part.strip_prefix('m') {
+                    let (x, y) = value.split_once(',')?;
+                    input.mouse_input = Some((x.parse().ok()?, y.parse().ok()?));
+                } else if let Some(value) = part.strip_prefix('t') {
+                    for code in value.split(',') {
+                        input.text_input.push(char::from_u32(code.parse().ok()?)?);
+                    }
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+
+    Some((tick, input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::input::{InputAction, InputDispatcher};
+    use crate::input::controllers::camera_controller::CameraController;
+    use crate::input::source::ScriptedInputSource;
+    use crate::rendering::camera::Camera;
+    use crate::settings::Settings;
+
+    //Unique to the test and the process, so tests running side by side don't share a file
+    fn recording_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("kardashev_{}_{}.txt", name, std::process::id()))
+    }
+
+    //Runs every frame of the source through a camera controller, moving the camera the way the
+    //game does, with the dispatcher optionally recording
+    fn drive_camera(source: Box<dyn InputSource>, frames: usize, recorder: Option<InputRecorder>) -> Camera {
+        let settings = Settings::new();
+        let mut dispatcher = InputDispatcher::new(source);
+        dispatcher.set_controller(Box::new(CameraController::new(&settings)));
+        if let Some(recorder) = recorder {
+            dispatcher.record(recorder);
+        }
+
+        let mut camera = Camera::new();
+        for _ in 0..frames {
+            for action in dispatcher.update().unwrap() {
+                match action {
+                    InputAction::MoveCamera(delta) => camera.move_by(delta),
+                    InputAction::LookDelta(delta) => camera.apply_look(delta),
+                    _ => {}
+                }
+            }
+        }
+        camera
+    }
+
+    #[test]
+    fn frames_survive_formatting_and_parsing() {
+        let mut input = FrameInput::empty();
+        input.keys_input.extend([
+            Input::KeyPressed(Keycode::W),
+            Input::KeyHeld(Keycode::W),
+            Input::KeyReleased(Keycode::LSHIFT),
+            Input::ButtonPressed(MouseButton::Left),
+            Input::ButtonHeld(MouseButton::Left),
+            Input::ButtonReleased(MouseButton::Right),
+            Input::WheelUp,
+            Input::WheelDown,
+            Input::FocusGained,
+            Input::FocusLost
+        ]);
+        input.mouse_input = Some((12, -7));
+        input.text_input = "say hello, world".to_string();
+        input.quit_requested = true;
+
+        let line = format_frame(42, &input);
+        let (tick, parsed) = parse_frame(&line).unwrap();
+        assert_eq!(tick, 42);
+        assert_eq!(parsed.keys_input, input.keys_input);
+        assert_eq!(parsed.mouse_input, input.mouse_input);
+        assert_eq!(parsed.text_input, input.text_input);
+        assert!(parsed.quit_requested);
+        assert_eq!(format_frame(tick, &parsed), line);
+    }
+
+    #[test]
+    fn malformed_frames_are_rejected() {
+        assert!(parse_frame("").is_none());
+        assert!(parse_frame("x p119").is_none());
+        assert!(parse_frame("3 zz").is_none());
+        assert!(parse_frame("3 m12").is_none());
+        assert!(parse_frame("3 pnope").is_none());
+    }
+
+    #[test]
+    fn replay_moves_the_camera_the_same_as_the_recorded_session() {
+        //Walk forward for two frames, turn a quarter to the right, then strafe right for one
+        let frames = vec![
+            vec![RawInput::KeyDown(Keycode::W)],
+            vec![],
+            vec![RawInput::KeyUp(Keycode::W), RawInput::MouseMotion((157, 0))],
+            vec![RawInput::KeyDown(Keycode::D)],
+            vec![RawInput::KeyUp(Keycode::D), RawInput::Text("hi".to_string())],
+            vec![]
+        ];
+        let frame_count = frames.len();
+        let path = recording_path("replay_camera");
+
+        let recorder = InputRecorder::create(&path, 7).unwrap();
+        let recorded = drive_camera(Box::new(ScriptedInputSource::with_frames(frames)), frame_count, Some(recorder));
+
+        let replay = InputReplay::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.seed, 7);
+        let source = ReplayInputSource::new(replay, Box::new(ScriptedInputSource::new()));
+        let replayed = drive_camera(Box::new(source), frame_count, None);
+
+        assert_eq!(replayed.position(), recorded.position());
+        assert_eq!(replayed.yaw(), recorded.yaw());
+        //Two steps along +x, then after turning to face +z, one step to the right along -x
+        assert!(replayed.position().abs_diff_eq(Vec3::new(0.1, 0.0, 3.0), 1e-3), "{}", replayed.position());
+    }
+}
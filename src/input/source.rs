@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
+use sdl2::EventPump;
+use super::controllers::MouseMotion;
+
+//One thing that happened since the last poll, before the dispatcher has worked out which keys are
+//pressed, held or released
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RawInput {
+    KeyDown(Keycode),
+    KeyUp(Keycode),
+    ButtonDown(MouseButton),
+    ButtonUp(MouseButton),
+    MouseMotion(MouseMotion),
+    //Positive scrolls away from the player
+    Wheel(i32),
+    Text(String),
+    FocusGained,
+    FocusLost,
+    Quit
+}
+
+//Where the InputDispatcher gets its input from, polled once a frame
+pub trait InputSource {
+    fn poll(&mut self) -> Vec<RawInput>;
+}
+
+//The real keyboard and mouse
+pub struct SdlInputSource {
+    event_pump: EventPump
+}
+
+impl SdlInputSource {
+    pub fn new(event_pump: EventPump) -> Self {
+        Self { event_pump }
+    }
+}
+
+impl InputSource for SdlInputSource {
+    fn poll(&mut self) -> Vec<RawInput> {
+        self.event_pump.poll_iter().filter_map(|event| match event {
+            Event::Quit { .. } => Some(RawInput::Quit),
+            //Key repeats aren't new presses, and holding is tracked by the dispatcher anyway
+            Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => Some(RawInput::KeyDown(keycode)),
+            Event::KeyUp { keycode: Some(keycode), .. } => Some(RawInput::KeyUp(keycode)),
+            Event::MouseButtonDown { mouse_btn, .. } => Some(RawInput::ButtonDown(mouse_btn)),
+            Event::MouseButtonUp { mouse_btn, .. } => Some(RawInput::ButtonUp(mouse_btn)),
+            Event::MouseMotion { xrel, yrel, .. } => Some(RawInput::MouseMotion((xrel, yrel))),
+            //Natural scrolling reports flipped, so undo it to keep the wheel meaning the same thing
+            Event::MouseWheel { y, direction: MouseWheelDirection::Flipped, .. } => Some(RawInput::Wheel(-y)),
+            Event::MouseWheel { y, .. } => Some(RawInput::Wheel(y)),
+            Event::TextInput { text, .. } => Some(RawInput::Text(text)),
+            Event::Window { win_event: WindowEvent::FocusGained, .. } => Some(RawInput::FocusGained),
+            Event::Window { win_event: WindowEvent::FocusLost, .. } => Some(RawInput::FocusLost),
+            _ => None
+        }).collect()
+    }
+}
+
+//Hands out a prepared list of frames, one per poll, then nothing. Lets the input pipeline run
+//without a window
+pub struct ScriptedInputSource {
+    frames: VecDeque<Vec<RawInput>>
+}
+
+impl ScriptedInputSource {
+    pub fn new() -> Self {
+        Self { frames: VecDeque::new() }
+    }
+
+    pub fn with_frames(frames: impl IntoIterator<Item = Vec<RawInput>>) -> Self {
+        Self { frames: frames.into_iter().collect() }
+    }
+
+    pub fn push_frame(&mut self, frame: Vec<RawInput>) {
+        self.frames.push_back(frame);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl Default for ScriptedInputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputSource for ScriptedInputSource {
+    fn poll(&mut self) -> Vec<RawInput> {
+        self.frames.pop_front().unwrap_or_default()
+    }
+}
@@ -0,0 +1,12 @@
+//The game as a library, so benchmarks can drive the world, meshing and events without a window.
+
+pub mod assets;
+pub mod error;
+pub mod input;
+pub mod rendering;
+pub mod world;
+pub mod debug;
+pub mod events;
+pub mod logging;
+pub mod settings;
+pub mod state;
@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+//How many records are kept before the oldest are dropped
+pub const LOG_CAPACITY: usize = 512;
+//Used when RUST_LOG isn't set, so warnings always get through
+pub const DEFAULT_LOG_LEVEL: &str = "warn";
+
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    //Counts up from 1 for every record pushed, so readers can tell which ones they have seen
+    pub id: u64,
+    pub time: Instant,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    //Every field other than the message, in the order they were written
+    pub fields: Vec<(String, String)>
+}
+
+impl LogRecord {
+    pub fn new(level: Level, target: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            time: Instant::now(),
+            level,
+            target: target.into(),
+            message: message.into(),
+            fields: Vec::new()
+        }
+    }
+}
+
+//The message followed by its fields, like `Saved world path="saves/1"`
+impl Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        for (name, value) in &self.fields {
+            write!(f, " {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+//Which records to show. Levels are tracing's, where a more verbose level is greater, so a record
+//passes if its level is no more verbose than the filter's
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    pub level: Level,
+    //Matches the target and everything under it, so `kardashev::world` includes its submodules
+    pub target: Option<String>
+}
+
+impl LogFilter {
+    pub fn new(level: Level) -> Self {
+        Self { level, target: None }
+    }
+
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        let target_matches = match &self.target {
+            Some(target) => record.target == *target || record.target.starts_with(&format!("{}::", target)),
+            None => true
+        };
+        record.level <= self.level && target_matches
+    }
+}
+
+struct Records {
+    next_id: u64,
+    records: VecDeque<LogRecord>
+}
+
+//Recent log records shared between the tracing layer that fills it and whatever displays it.
+//Cloning gives another handle to the same records. Nothing here touches the renderer, so it can be
+//filled and read back on its own
+#[derive(Clone)]
+pub struct LogBuffer {
+    records: Arc<Mutex<Records>>,
+    capacity: usize
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self::with_capacity(LOG_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: Arc::new(Mutex::new(Records { next_id: 1, records: VecDeque::with_capacity(capacity) })),
+            capacity
+        }
+    }
+
+    //Fills in the record's id, dropping the oldest record if the buffer is full
+    pub fn push(&self, mut record: LogRecord) {
+        let mut records = self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        record.id = records.next_id;
+        records.next_id += 1;
+
+        if records.records.len() == self.capacity {
+            records.records.pop_front();
+        }
+        records.records.push_back(record);
+    }
+
+    //Oldest first
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.matching(|_| true)
+    }
+
+    pub fn filtered(&self, filter: &LogFilter) -> Vec<LogRecord> {
+        self.matching(|record| filter.matches(record))
+    }
+
+    //Records pushed after the one with the given id, oldest first
+    pub fn since(&self, id: u64) -> Vec<LogRecord> {
+        self.matching(|record| record.id > id)
+    }
+
+    fn matching(&self, predicate: impl Fn(&LogRecord) -> bool) -> Vec<LogRecord> {
+        let records = self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        records.records.iter().filter(|record| predicate(record)).cloned().collect()
+    }
+
+    pub fn layer(&self) -> LogBufferLayer {
+        LogBufferLayer { buffer: self.clone() }
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//Copies every event that gets past the subscriber's filter into a LogBuffer
+pub struct LogBufferLayer {
+    buffer: LogBuffer
+}
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut record = LogRecord::new(*metadata.level(), metadata.target(), String::new());
+        event.record(&mut FieldVisitor { record: &mut record });
+        self.buffer.push(record);
+    }
+}
+
+struct FieldVisitor<'a> {
+    record: &'a mut LogRecord
+}
+
+impl Visit for FieldVisitor<'_> {
+    //Strings are recorded separately so they don't pick up the quotes Debug would add to a message
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.record.message = value.to_string();
+        } else {
+            self.record.fields.push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.record.message = format!("{:?}", value);
+        } else {
+            self.record.fields.push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn messages(records: &[LogRecord]) -> Vec<&str> {
+        records.iter().map(|record| record.message.as_str()).collect()
+    }
+
+    #[test]
+    fn full_buffer_drops_the_oldest_record() {
+        let buffer = LogBuffer::with_capacity(3);
+        for message in ["one", "two", "three", "four"] {
+            buffer.push(LogRecord::new(Level::INFO, "kardashev", message));
+        }
+        assert_eq!(messages(&buffer.records()), ["two", "three", "four"]);
+    }
+
+    #[test]
+    fn ids_count_up_and_since_returns_newer_records() {
+        let buffer = LogBuffer::with_capacity(2);
+        for message in ["one", "two", "three"] {
+            buffer.push(LogRecord::new(Level::INFO, "kardashev", message));
+        }
+        let ids: Vec<u64> = buffer.records().iter().map(|record| record.id).collect();
+        assert_eq!(ids, [2, 3]);
+        assert_eq!(messages(&buffer.since(2)), ["three"]);
+        assert_eq!(messages(&buffer.since(0)), ["two", "three"]);
+        assert!(buffer.since(3).is_empty());
+    }
+
+    #[test]
+    fn filter_passes_its_level_and_less_verbose_ones() {
+        let filter = LogFilter::new(Level::INFO);
+        assert!(filter.matches(&LogRecord::new(Level::ERROR, "kardashev", "")));
+        assert!(filter.matches(&LogRecord::new(Level::WARN, "kardashev", "")));
+        assert!(filter.matches(&LogRecord::new(Level::INFO, "kardashev", "")));
+        assert!(!filter.matches(&LogRecord::new(Level::DEBUG, "kardashev", "")));
+        assert!(!filter.matches(&LogRecord::new(Level::TRACE, "kardashev", "")));
+    }
+
+    #[test]
+    fn filter_target_matches_whole_module_paths() {
+        let filter = LogFilter::new(Level::TRACE).with_target("kardashev::world");
+        assert!(filter.matches(&LogRecord::new(Level::INFO, "kardashev::world", "")));
+        assert!(filter.matches(&LogRecord::new(Level::INFO, "kardashev::world::light", "")));
+        assert!(!filter.matches(&LogRecord::new(Level::INFO, "kardashev::worldgen", "")));
+        assert!(!filter.matches(&LogRecord::new(Level::INFO, "kardashev", "")));
+    }
+
+    #[test]
+    fn layer_captures_message_and_fields() {
+        let buffer = LogBuffer::new();
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "kardashev::world", path = "saves/1", chunks = 4, "Saved world");
+        });
+
+        let records = buffer.records();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.level, Level::WARN);
+        assert_eq!(record.target, "kardashev::world");
+        assert_eq!(record.message, "Saved world");
+        assert_eq!(record.fields, [("path".to_string(), "\"saves/1\"".to_string()), ("chunks".to_string(), "4".to_string())]);
+        assert_eq!(record.to_string(), "Saved world path=\"saves/1\" chunks=4");
+    }
+}
@@ -1,44 +1,92 @@
-mod input;
-mod rendering;
-mod world;
-mod debug;
-mod events;
-
-use input::controllers::{debug_overlay_controller::DebugOverlayController, composite_controller::CompositeController};
-use tracing::debug;
-use std::cell::RefCell;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::rc::Rc;
-use rendering::render_context::RenderContext;
-use rendering::text::Surface2D;
-use sdl2::pixels::Color;
-use debug::DebugOverlay;
+use kardashev::assets::Assets;
+use kardashev::error::{report, AppError};
+use tracing::{debug, error, warn};
+use kardashev::debug::{profiler, DebugOverlay};
 use gl;
-use input::{controllers::camera_controller::CameraController, InputAction, InputDispatcher};
-use rendering::{text, camera::Camera};
-use world::chunk_mesh_manager::ChunkMeshManager;
-use world::World;
+use kardashev::input::{InputAction, InputDispatcher, InputError};
+use kardashev::input::recording::{InputRecorder, InputReplay, ReplayInputSource, FIXED_TIMESTEP};
+use kardashev::input::source::{InputSource, SdlInputSource};
+use kardashev::logging::{LogBuffer, DEFAULT_LOG_LEVEL};
+use kardashev::rendering::{self, Renderer};
+use sdl2::mouse::MouseUtil;
+use kardashev::settings::Settings;
+use kardashev::state::{main_menu::MainMenu, AppContext, StateMachine};
 use tracing_subscriber::{fmt, EnvFilter};
 use tracing_subscriber::reload;
-use world::chunk::{CHUNK_SIZE, VOXEL_SIZE};
+use kardashev::world::block::BlockRegistry;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 
-pub fn main() -> Result<(), String> {
+pub fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            report_failure(&error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+//Logs the whole error chain and, since a player launching the game normally won't see a terminal,
+//tries to show it in a message box as well
+fn report_failure(error: &AppError) {
+    let report = report(error);
+    error!("{}", report);
+    eprintln!("Kardashev has stopped: {}", report);
+
+    if let Err(message_box_error) = sdl2::messagebox::show_simple_message_box(
+        sdl2::messagebox::MessageBoxFlag::ERROR,
+        "Kardashev has stopped",
+        &report,
+        None
+    ) {
+        eprintln!("Unable to show the error in a message box: {}", message_box_error);
+    }
+}
+
+//Passed on the command line to record a session to a file, or to play one back
+#[derive(Default)]
+struct Arguments {
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>
+}
+
+fn parse_arguments() -> Arguments {
+    let mut arguments = Arguments::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => arguments.record = args.next().map(PathBuf::from),
+            "--replay" => arguments.replay = args.next().map(PathBuf::from),
+            _ => warn!("Ignoring unknown argument {}", arg)
+        }
+    }
+    arguments
+}
+
+fn run() -> Result<(), AppError> {
     //Start by setting up logging...
-    let filter = EnvFilter::from_default_env();
+    let log_filter = std::env::var(EnvFilter::DEFAULT_ENV).unwrap_or_else(|_| DEFAULT_LOG_LEVEL.to_string());
+    let filter = EnvFilter::builder().parse_lossy(&log_filter);
     let (filter_layer, filter_handle) = reload::Layer::new(filter);
+    let log = LogBuffer::new();
 
     let subscriber = tracing_subscriber::registry()
     .with(filter_layer)
-    .with(fmt::layer());
+    .with(fmt::layer())
+    .with(log.layer());
 
-    tracing::subscriber::set_global_default(subscriber).unwrap();
+    tracing::subscriber::set_global_default(subscriber)?;
 
     debug!(target: "sdl2", "Initialising SDL2...");
-    let sdl_context = sdl2::init()?;
+    let sdl_context = sdl2::init().map_err(AppError::sdl("initialise SDL2"))?;
     debug!(target: "sdl2", "SDL2 Initialised.");
 
     debug!(target: "video_subsystem", "Attempting to create video subsystem from SDL context...");
-    let video_subsystem = sdl_context.video()?;
+    let video_subsystem = sdl_context.video().map_err(AppError::sdl("create the video subsystem"))?;
     debug!(target: "video_subsystem", "Video subsystem created with no issues.");
 
     debug!(target: "window", "Creating window from video subsystem...");
@@ -46,99 +94,113 @@ pub fn main() -> Result<(), String> {
         .window("Kardashev", 800, 600)
         .opengl()
         .build()
-        .map_err(|e| e.to_string())?;
+        .map_err(|error| AppError::sdl("create a window")(error.to_string()))?;
     debug!(target: "window", "Window created from video subsystem");
 
     debug!(target: "opengl", "Attempting to establish an OpenGL context in our window...");
-    let _gl_context = window.gl_create_context().unwrap();
+    let _gl_context = window.gl_create_context().map_err(AppError::sdl("create an OpenGL context"))?;
     debug!(target: "opengl", "OpenGL context created. Function will be loaded afterwards, but no debugging support can be provided due to OpenGL using raw C.");
     let _gl = gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const std::os::raw::c_void);
 
-    window.set_mouse_grab(true);
-    sdl_context.mouse().capture(true);
-    sdl_context.mouse().show_cursor(false);
-    sdl_context.mouse().set_relative_mouse_mode(true);
-
-    debug!("Setting up SDL2 ttf context...");
-    let sdl2_ttf = sdl2::ttf::init().expect("Failed to initialise the sdl2 ttf context!");
-    debug!("Established SDL2 ttf context, loading fonts...");
-    let font = sdl2_ttf.load_font("assets/fonts/FiraCode-SemiBold.tff", 12).expect("Failed to import font.");
-
     debug!("Creating event pump from SDL context...");
-    let event_pump = sdl_context.event_pump().unwrap();
+    let event_pump = sdl_context.event_pump().map_err(AppError::sdl("create an event pump"))?;
     debug!("Event pump created from SDL context");
+    //Typed text comes through as its own events, for the console and menus
+    video_subsystem.text_input().start();
 
-    debug!(target: "kardashev_startup", "Creating Kardashev world requirements...");
-    let mut camera = Camera::new();
-    let mut debugger = DebugOverlay::new(filter_handle);
-    let mut renderer = rendering::init(&mut window);
-    let mut input_handler = InputDispatcher::new(event_pump);
-    let mut event_queue = events::EventQueue::new();
-    let camera_controller = Box::new(CameraController::new());
-    let debug_controller = Box::new(DebugOverlayController{});
-    let mut controller = CompositeController::new();
-
-    controller.push_controller(camera_controller);
-    controller.push_controller(debug_controller);
-
-    let world = Rc::new(RefCell::new(World::new()));
-    let chunk_mesh_manager = Rc::new(RefCell::new(ChunkMeshManager::new()));
-    debug!(target: "kardashev_startup", "Kardashev world requirements created.");
+    debug!(target: "kardashev_startup", "Creating Kardashev application requirements...");
+    let mouse = sdl_context.mouse();
+    let blocks = Rc::new(BlockRegistry::new());
+    let assets = Rc::new(Assets::new());
+    let mut renderer = rendering::init(&mut window, &blocks, assets.clone())?;
 
-    debug!(target: "kardashev_startup", "Linking up Kardashev world requirements.");
-    event_queue.register_handler(chunk_mesh_manager.clone());
-    input_handler.set_controller(controller);
-
-    'main: loop {
+    debug!("Setting up SDL2 ttf context...");
+    let sdl2_ttf = sdl2::ttf::init().map_err(|error| AppError::sdl("initialise SDL2 ttf")(error.to_string()))?;
+    debug!("Established SDL2 ttf context, rasterising fonts...");
+    let font = renderer.fonts_mut().load(&sdl2_ttf, &assets, "fonts/FiraCode-SemiBold.tff", 12)?;
+    let title_font = renderer.fonts_mut().load(&sdl2_ttf, &assets, "fonts/FiraCode-SemiBold.tff", 24)?;
+    //Replays reuse the seed they were recorded with. Recordings pick theirs now, so it can be written
+    //down before the world exists
+    let arguments = parse_arguments();
+    let mut random_seed = None;
+    let mut input_source: Box<dyn InputSource> = Box::new(SdlInputSource::new(event_pump));
+    if let Some(path) = &arguments.replay {
+        let replay = InputReplay::read(path).map_err(InputError::from)?;
+        random_seed = Some(replay.seed);
+        input_source = Box::new(ReplayInputSource::new(replay, input_source));
+    }
+    let mut input_handler = InputDispatcher::new(input_source);
+    if let Some(path) = &arguments.record {
+        let seed = *random_seed.get_or_insert_with(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0)
+        });
+        input_handler.record(InputRecorder::create(path, seed).map_err(InputError::from)?);
+    }
+    //A replay only matches its recording if every frame advances the world by the same amount
+    let fixed_timestep = random_seed.is_some();
+
+    let mut ctx = AppContext {
+        font,
+        title_font,
+        debugger: DebugOverlay::new(filter_handle, log_filter, log.clone()),
+        settings: Settings::new(),
+        blocks,
+        log,
+        random_seed,
+        frame_time: std::time::Duration::ZERO
+    };
+    let mut state_machine = StateMachine::new(Box::new(MainMenu::new()));
+    debug!(target: "kardashev_startup", "Kardashev application requirements created.");
+
+    activate_current_state(&state_machine, &ctx, &mut input_handler, &mut renderer, &mouse);
+
+    while state_machine.is_running() {
         let frame_start = std::time::Instant::now();
-        world.borrow_mut().update(camera.position(), &mut event_queue);        
-        event_queue.dispatch_events();
-
-        let text = format!("Frame: {:.2} ms|Chunks: {}|Draws: {}|Cam: ({:.1}, {:.1}, {:.1}) Yaw: {:.1} Pitch: {:.1}",
-            debugger.frame_time_ms,
-            debugger.chunk_count,
-            debugger.draw_calls,
-            debugger.camera_position.x,
-            debugger.camera_position.y,
-            debugger.camera_position.z,
-            debugger.camera_yaw.to_degrees(),
-            debugger.camera_pitch.to_degrees()
-        );
-
-        let texture = text::create_text_texture(&font, &text, Color::WHITE);
-        let quad = text::new_text_quad();
-
-        let fps = Surface2D {quad, texture};
-
-        let mut quads = Vec::new();
-        quads.push(&fps);
-
-        for action in input_handler.update().expect("Error in input handling loop!") {
-            match action {
-                InputAction::Quit => break 'main,
-                InputAction::MoveCamera(delta) => camera.move_by(delta),
-                InputAction::LookDelta(relative_direction) => camera.apply_look(relative_direction),
-                InputAction::ToggleDebugModule(1) => debugger.toggle_module("kardashev::rendering"),
-                _ => {}
-            }
+        profiler::begin_frame();
+
+        let actions = {
+            let _scope = profiler::scope("input");
+            input_handler.update()?
+        };
+        if actions.iter().any(|action| matches!(action, InputAction::Screenshot)) {
+            renderer.request_screenshot();
         }
-
-        let mesh_ref = chunk_mesh_manager.borrow();
-        let meshes = mesh_ref.meshes();
-
-        {
-            let render_context = RenderContext {
-                camera: &camera,
-                meshes,
-                quads 
-            };
-            renderer.render(render_context);
+        let transition = {
+            let _scope = profiler::scope("update");
+            state_machine.update(actions, &mut ctx)
+        };
+        ctx.debugger.toasts.update();
+        if state_machine.apply(transition) {
+            activate_current_state(&state_machine, &ctx, &mut input_handler, &mut renderer, &mouse);
         }
 
-        let frame_duration = frame_start.elapsed();
-        debugger.update(frame_duration, &camera);
+        renderer.reload_changed_shaders();
+        renderer.begin_frame();
+        state_machine.render(&mut renderer, &ctx);
+        //Drawn last so they sit over every state, menus included
+        let line_height = renderer.fonts().line_height(ctx.font);
+        renderer.render_2d(&ctx.debugger.toasts.draws(ctx.font, line_height));
+        renderer.end_frame();
+
+        profiler::end_frame();
+        ctx.frame_time = if fixed_timestep { FIXED_TIMESTEP } else { frame_start.elapsed() };
     }
 
     Ok(())
 }
 
+fn activate_current_state(state_machine: &StateMachine, ctx: &AppContext, input_handler: &mut InputDispatcher, renderer: &mut Renderer, mouse: &MouseUtil) {
+    let Some(state) = state_machine.current() else {
+        return;
+    };
+
+    debug!("Activating state {}.", state.name());
+    input_handler.set_controller(state.controller(&ctx.settings));
+
+    //Only grab the mouse while something actually wants mouse look
+    let captured = state.captures_mouse();
+    renderer.set_mouse_grab(captured);
+    mouse.capture(captured);
+    mouse.show_cursor(!captured);
+    mouse.set_relative_mouse_mode(captured);
+}
@@ -82,10 +82,33 @@ impl Camera {
         }
     }
 
+    pub fn with_pose(position: Vec3, yaw: f32, pitch: f32) -> Camera {
+        let mut camera = Camera::new();
+        camera.position = position;
+        camera.yaw = yaw;
+        camera.pitch = pitch;
+
+        //Applying no look delta recalculates our forward vector from the yaw and pitch
+        camera.apply_look((0.0, 0.0));
+        camera
+    }
+
     pub fn position(&self) -> Vec3 {
         self.position
     }
 
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.forward
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.up
+    }
+
     pub fn yaw(&self) -> f32 {
         self.yaw
     }
@@ -95,6 +118,12 @@ impl Camera {
     }
 }
 
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Lens {
     pub fn new() -> Lens {
         Self {
@@ -104,4 +133,14 @@ impl Lens {
             z_far: 100.0
         }
     }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.aspect_ratio = aspect_ratio;
+    }
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Self::new()
+    }
 }
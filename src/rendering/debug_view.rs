@@ -0,0 +1,161 @@
+use glam::{Mat4, Vec3};
+use sdl2::pixels::Color;
+use strum_macros::Display;
+use crate::rendering::error::GpuError;
+use crate::rendering::gpu::{self, GpuResource, GpuResourceKind};
+use crate::rendering::mesh::{Mesh, VertexAttribute, VertexLayout};
+use crate::rendering::shaders::Shader;
+use crate::world::chunk::chunk_bounds;
+
+//What the 3D pass draws, switched at runtime from the debug controller
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+pub enum RenderDebugMode {
+    Off,
+    Wireframe,
+    WireframeOverlay,
+    Normals,
+    ChunkBoundaries,
+    Overdraw
+}
+
+impl RenderDebugMode {
+    const ALL: [RenderDebugMode; 6] = [
+        RenderDebugMode::Off,
+        RenderDebugMode::Wireframe,
+        RenderDebugMode::WireframeOverlay,
+        RenderDebugMode::Normals,
+        RenderDebugMode::ChunkBoundaries,
+        RenderDebugMode::Overdraw
+    ];
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+//Handed to default.frag as defines, and picked between with its view_mode uniform
+pub const VIEW_SHADED: i32 = 0;
+pub const VIEW_FLAT: i32 = 1;
+pub const VIEW_NORMALS: i32 = 2;
+pub const VIEW_OVERDRAW: i32 = 3;
+
+pub fn shader_defines() -> Vec<(String, String)> {
+    vec![
+        ("VIEW_SHADED".to_string(), VIEW_SHADED.to_string()),
+        ("VIEW_FLAT".to_string(), VIEW_FLAT.to_string()),
+        ("VIEW_NORMALS".to_string(), VIEW_NORMALS.to_string()),
+        ("VIEW_OVERDRAW".to_string(), VIEW_OVERDRAW.to_string()),
+    ]
+}
+
+//Just a position, drawn with the default shader which leaves its other inputs at their defaults
+pub const LINE_LAYOUT: VertexLayout = VertexLayout {
+    name: "line",
+    attributes: &[
+        VertexAttribute { name: "pos", location: 0, components: 3 },
+    ]
+};
+
+//The twelve edges of a chunk, drawn as lines with the chunk's model matrix
+pub fn chunk_box_mesh() -> Result<Mesh, GpuError> {
+    let (min, max) = chunk_bounds((0, 0, 0));
+
+    let mut vertices = Vec::with_capacity(8 * 3);
+    for corner in 0..8 {
+        vertices.push(if corner & 1 == 0 { min.x } else { max.x });
+        vertices.push(if corner & 2 == 0 { min.y } else { max.y });
+        vertices.push(if corner & 4 == 0 { min.z } else { max.z });
+    }
+
+    //Each edge joins two corners that differ in exactly one axis
+    let mut indices = Vec::with_capacity(12 * 2);
+    for corner in 0..8u32 {
+        for axis in [1, 2, 4] {
+            if corner & axis == 0 {
+                indices.extend_from_slice(&[corner, corner | axis]);
+            }
+        }
+    }
+
+    Mesh::from_vertices_and_indices(&vertices, &indices, &LINE_LAYOUT)
+}
+
+//World space lines from the debug draw API, kept in one buffer that lives as long as the renderer.
+//The buffer only grows, so most frames just overwrite the start of it. The line layout has no
+//colour, so lines are sorted by colour and each run is drawn with the shader's colour uniform
+pub struct DebugLines {
+    vao: GpuResource,
+    vbo: GpuResource,
+    //How many floats the buffer has room for
+    capacity: usize
+}
+
+impl DebugLines {
+    pub fn new() -> Result<Self, GpuError> {
+        let vao = GpuResource::new(GpuResourceKind::VertexArray)?;
+        let vbo = GpuResource::new(GpuResourceKind::Buffer)?;
+
+        unsafe {
+            gl::BindVertexArray(vao.id());
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo.id());
+
+            LINE_LAYOUT.enable_attributes();
+
+            gl::BindVertexArray(0);
+        }
+
+        Ok(Self { vao, vbo, capacity: 0 })
+    }
+
+    //Expects the default shader to be bound. Returns the number of draw calls issued
+    pub fn draw(&mut self, shader: &Shader, lines: &[(Vec3, Vec3, Color)]) -> usize {
+        let mut lines: Vec<&(Vec3, Vec3, Color)> = lines.iter().collect();
+        lines.sort_by_key(|(_, _, color)| (color.r, color.g, color.b));
+
+        let mut vertices = Vec::with_capacity(lines.len() * 6);
+        //Colour, first vertex and vertex count of each run
+        let mut runs: Vec<(Color, i32, i32)> = Vec::new();
+        for (from, to, color) in lines {
+            match runs.last_mut() {
+                Some((run_color, _, count)) if (run_color.r, run_color.g, run_color.b) == (color.r, color.g, color.b) => *count += 2,
+                _ => runs.push((*color, (vertices.len() / 3) as i32, 2))
+            }
+            vertices.extend_from_slice(&[from.x, from.y, from.z, to.x, to.y, to.z]);
+        }
+
+        if vertices.is_empty() {
+            return 0;
+        }
+
+        unsafe {
+            gl::BindVertexArray(self.vao.id());
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo.id());
+            if vertices.len() > self.capacity {
+                self.capacity = vertices.len().next_power_of_two();
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (self.capacity * std::mem::size_of::<f32>()) as isize,
+                    std::ptr::null(),
+                    gl::DYNAMIC_DRAW
+                );
+            }
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                std::mem::size_of_val(vertices.as_slice()) as isize,
+                vertices.as_ptr().cast()
+            );
+
+            shader.set_mat4("model", &Mat4::IDENTITY);
+            for &(color, first, count) in &runs {
+                shader.set_vec3("color", Vec3::new(color.r as f32, color.g as f32, color.b as f32) / 255.0);
+                gl::DrawArrays(gl::LINES, first, count);
+            }
+            shader.set_vec3("color", Vec3::ONE);
+        }
+
+        gpu::count_draw_calls(runs.len());
+        runs.len()
+    }
+}
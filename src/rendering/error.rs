@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use thiserror::Error;
+use crate::assets::AssetError;
+use crate::rendering::gpu::GpuResourceKind;
+
+#[derive(Debug, Error)]
+#[error("OpenGL was unable to create a {0:?}")]
+pub struct GpuError(pub GpuResourceKind);
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("Unable to create an OpenGL object")]
+    Gpu(#[from] GpuError),
+    #[error("Unable to build a shader")]
+    Shader(#[from] ShaderError),
+    #[error("Unable to load textures")]
+    Texture(#[from] TextureError),
+    #[error("Shadow map framebuffer is incomplete (status {status:#x})")]
+    IncompleteShadowMap { status: u32 },
+    #[error("Render target framebuffer is incomplete (status {status:#x})")]
+    IncompleteRenderTarget { status: u32 }
+}
+
+//Everything here names the shader file at fault, since one program is built from several
+#[derive(Debug, Error)]
+pub enum ShaderError {
+    #[error("Unable to read shader source")]
+    Source(#[from] AssetError),
+    #[error("Shader include cycle: {0}")]
+    IncludeCycle(String),
+    #[error("{file}:{line}: #include needs a file name in quotes")]
+    MissingIncludeName { file: String, line: usize },
+    #[error("{name} failed to compile: {log}")]
+    Compile { name: String, log: String },
+    #[error("OpenGL was unable to create a shader program for {name}")]
+    CreateProgram { name: String },
+    #[error("{name} failed to link: {log}")]
+    Link { name: String, log: String },
+    #[error("{name}: input {input} has type {kind}, which a vertex layout can't supply")]
+    UnsupportedInput { name: String, input: String, kind: String },
+    #[error("{name}: input {input} has location {location}, which is out of range")]
+    InvalidLocation { name: String, input: String, location: String },
+    #[error("{name}: input {input} expects {components} components at location {location}, but the {layout} vertex layout has {attribute} with {attribute_components} components at location {attribute_location}")]
+    LayoutMismatch {
+        name: String,
+        input: String,
+        components: i32,
+        location: u32,
+        layout: &'static str,
+        attribute: &'static str,
+        attribute_components: i32,
+        attribute_location: u32
+    },
+    #[error("{name}: input {input} at location {location} isn't supplied by any vertex layout the shader draws: [{}]", .layouts.join(", "))]
+    UnsuppliedInput { name: String, input: String, location: u32, layouts: Vec<&'static str> },
+    #[error("{name}: input {input} was linked at location {location} without a layout(location = N) qualifier")]
+    UnqualifiedInput { name: String, input: String, location: u32 }
+}
+
+#[derive(Debug, Error)]
+pub enum TextureError {
+    #[error("Unable to read texture")]
+    Source(#[from] AssetError),
+    #[error("Unable to upload texture")]
+    Gpu(#[from] GpuError),
+    #[error("Unable to decode {name}")]
+    Decode { name: String, #[source] source: png::DecodingError },
+    #[error("{name} is still indexed after expansion")]
+    Indexed { name: String },
+    #[error("Texture array layers must be {size}x{size}, got {width}x{height}")]
+    LayerSize { size: u32, width: u32, height: u32 },
+    #[error("Unable to write PNG {}", .path.display())]
+    Encode { path: PathBuf, #[source] source: png::EncodingError }
+}
+
+//SDL_ttf only reports errors as strings, so those are kept as messages
+#[derive(Debug, Error)]
+pub enum FontError {
+    #[error("Unable to read font")]
+    Source(#[from] AssetError),
+    #[error("Unable to upload the glyph atlas")]
+    Gpu(#[from] GpuError),
+    #[error("Unable to load font {name}: {message}")]
+    Load { name: String, message: String },
+    #[error("Unable to rasterise glyph {character:?}: {message}")]
+    Rasterise { character: char, message: String },
+    #[error("Unable to read pixels for glyph {0:?}")]
+    GlyphPixels(char)
+}
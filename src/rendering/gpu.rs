@@ -0,0 +1,125 @@
+use std::cell::Cell;
+use strum::{EnumCount, IntoEnumIterator};
+use strum_macros::{Display, EnumCount, EnumIter};
+use tracing::trace;
+use crate::rendering::error::GpuError;
+
+//Every kind of OpenGL object the renderer keeps around. Shader stages aren't here, since they are
+//deleted as soon as their program has linked
+#[derive(Clone, Copy, Debug, Display, EnumCount, EnumIter, Eq, Hash, PartialEq)]
+pub enum GpuResourceKind {
+    #[strum(to_string = "VAO")]
+    VertexArray,
+    #[strum(to_string = "Buf")]
+    Buffer,
+    #[strum(to_string = "Tex")]
+    Texture,
+    #[strum(to_string = "FBO")]
+    Framebuffer,
+    #[strum(to_string = "Prog")]
+    Program
+}
+
+//A GL context is only ever current on one thread, so counting per thread counts per context. It
+//also keeps tests that run side by side from seeing each other's resources
+thread_local! {
+    //How many of each kind are alive right now, indexed by kind
+    static LIVE: [Cell<usize>; GpuResourceKind::COUNT] = const { [const { Cell::new(0) }; GpuResourceKind::COUNT] };
+    //Draw calls issued since the current frame began, and the total for the last whole frame
+    static DRAW_CALLS: Cell<usize> = const { Cell::new(0) };
+    static LAST_FRAME_DRAW_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+//How many of each kind were alive at one moment. Take one before and after something to see what
+//it created or freed
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GpuCounts([usize; GpuResourceKind::COUNT]);
+
+impl GpuCounts {
+    pub fn get(&self, kind: GpuResourceKind) -> usize {
+        self.0[kind as usize]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (GpuResourceKind, usize)> + '_ {
+        GpuResourceKind::iter().map(|kind| (kind, self.get(kind)))
+    }
+
+    //How many more of each kind are alive now than in the earlier snapshot, negative for freed
+    pub fn since(&self, earlier: &GpuCounts) -> Vec<(GpuResourceKind, isize)> {
+        GpuResourceKind::iter().map(|kind| (kind, self.get(kind) as isize - earlier.get(kind) as isize)).collect()
+    }
+}
+
+//Owns one OpenGL object and deletes it when dropped, so nothing the renderer makes can leak
+//without showing up in the live counts
+#[derive(Debug)]
+pub struct GpuResource {
+    kind: GpuResourceKind,
+    id: u32
+}
+
+impl GpuResource {
+    //OpenGL only fails to create objects when something is badly wrong, like no current context,
+    //and that is passed up so the game can report it rather than crash
+    pub fn new(kind: GpuResourceKind) -> Result<Self, GpuError> {
+        let mut id = 0;
+        unsafe {
+            match kind {
+                GpuResourceKind::VertexArray => gl::GenVertexArrays(1, &mut id),
+                GpuResourceKind::Buffer => gl::GenBuffers(1, &mut id),
+                GpuResourceKind::Texture => gl::GenTextures(1, &mut id),
+                GpuResourceKind::Framebuffer => gl::GenFramebuffers(1, &mut id),
+                GpuResourceKind::Program => id = gl::CreateProgram()
+            }
+        }
+        if id == 0 {
+            return Err(GpuError(kind));
+        }
+
+        LIVE.with(|live| live[kind as usize].set(live[kind as usize].get() + 1));
+        trace!("Created {:?} {}.", kind, id);
+        Ok(Self { kind, id })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Drop for GpuResource {
+    fn drop(&mut self) {
+        unsafe {
+            match self.kind {
+                GpuResourceKind::VertexArray => gl::DeleteVertexArrays(1, &self.id),
+                GpuResourceKind::Buffer => gl::DeleteBuffers(1, &self.id),
+                GpuResourceKind::Texture => gl::DeleteTextures(1, &self.id),
+                GpuResourceKind::Framebuffer => gl::DeleteFramebuffers(1, &self.id),
+                GpuResourceKind::Program => gl::DeleteProgram(self.id)
+            }
+        }
+
+        LIVE.with(|live| live[self.kind as usize].set(live[self.kind as usize].get() - 1));
+        trace!("Deleted {:?} {}.", self.kind, self.id);
+    }
+}
+
+pub fn live(kind: GpuResourceKind) -> usize {
+    live_counts().get(kind)
+}
+
+pub fn live_counts() -> GpuCounts {
+    GpuCounts(LIVE.with(|live| live.each_ref().map(Cell::get)))
+}
+
+pub fn count_draw_calls(count: usize) {
+    DRAW_CALLS.with(|calls| calls.set(calls.get() + count));
+}
+
+//Called once the frame is presented, so draw_calls() always reports a complete frame
+pub fn end_frame_draw_calls() {
+    LAST_FRAME_DRAW_CALLS.with(|last| last.set(DRAW_CALLS.with(|calls| calls.take())));
+}
+
+pub fn draw_calls() -> usize {
+    LAST_FRAME_DRAW_CALLS.with(Cell::get)
+}
@@ -0,0 +1,35 @@
+use sdl2::video::{GLContext, GLProfile, Window};
+use sdl2::{Sdl, VideoSubsystem};
+
+//A hidden window with a current OpenGL context, for tests that need real GL objects. Tests using
+//it are #[ignore]d, since CI has no display. Run them somewhere that has one with
+//`cargo test -- --ignored`, or under Xvfb with `xvfb-run -a cargo test -- --ignored`
+pub struct HeadlessContext {
+    pub window: Window,
+    _gl_context: GLContext,
+    _video: VideoSubsystem,
+    _sdl: Sdl
+}
+
+impl HeadlessContext {
+    //Fails with SDL's error when there's no display or no OpenGL 3.3, which a test asked to run
+    //with --ignored should report rather than pass over
+    pub fn new() -> Result<Self, String> {
+        let sdl = sdl2::init()?;
+        let video = sdl.video()?;
+        let gl_attr = video.gl_attr();
+        gl_attr.set_context_profile(GLProfile::Core);
+        gl_attr.set_context_version(3, 3);
+
+        let window = video.window("Kardashev tests", 320, 240).opengl().hidden().build().map_err(|error| error.to_string())?;
+        let gl_context = window.gl_create_context()?;
+        gl::load_with(|s| video.gl_get_proc_address(s) as *const std::os::raw::c_void);
+
+        Ok(Self {
+            window,
+            _gl_context: gl_context,
+            _video: video,
+            _sdl: sdl
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use glam::Vec3;
+use crate::world::time::WorldClock;
+
+const DAY_ZENITH: Vec3 = Vec3::new(0.30, 0.55, 0.95);
+const DAY_HORIZON: Vec3 = Vec3::new(0.70, 0.85, 1.00);
+const SUNSET_ZENITH: Vec3 = Vec3::new(0.25, 0.30, 0.55);
+const SUNSET_HORIZON: Vec3 = Vec3::new(0.95, 0.55, 0.30);
+const NIGHT_ZENITH: Vec3 = Vec3::new(0.01, 0.01, 0.05);
+const NIGHT_HORIZON: Vec3 = Vec3::new(0.05, 0.07, 0.15);
+
+const DAY_SUN: Vec3 = Vec3::new(1.00, 0.97, 0.90);
+const SUNSET_SUN: Vec3 = Vec3::new(1.00, 0.60, 0.35);
+
+const DAY_AMBIENT: f32 = 0.35;
+const NIGHT_AMBIENT: f32 = 0.08;
+
+//Sun elevation (sine of the angle above the horizon) over which day fades through sunset into night
+const TWILIGHT: f32 = 0.2;
+
+pub struct Lighting {
+    pub sun_direction: Vec3,
+    pub sun_color: Vec3,
+    pub ambient_color: Vec3,
+    pub sky_zenith: Vec3,
+    pub sky_horizon: Vec3,
+    pub up: Vec3
+}
+
+impl Lighting {
+    pub fn from_clock(clock: &WorldClock, local_up: Vec3) -> Self {
+        let up = local_up.normalize();
+        let sun_direction = clock.sun_direction(up);
+        let elevation = sun_direction.dot(up);
+
+        //Day above the twilight band, night below it, sunset colours in the middle
+        let (sky_zenith, sky_horizon) = if elevation >= TWILIGHT {
+            (DAY_ZENITH, DAY_HORIZON)
+        } else if elevation >= 0.0 {
+            let t = elevation / TWILIGHT;
+            (SUNSET_ZENITH.lerp(DAY_ZENITH, t), SUNSET_HORIZON.lerp(DAY_HORIZON, t))
+        } else if elevation >= -TWILIGHT {
+            let t = (elevation + TWILIGHT) / TWILIGHT;
+            (NIGHT_ZENITH.lerp(SUNSET_ZENITH, t), NIGHT_HORIZON.lerp(SUNSET_HORIZON, t))
+        } else {
+            (NIGHT_ZENITH, NIGHT_HORIZON)
+        };
+
+        let daylight = smoothstep(-0.05, TWILIGHT, elevation);
+        let sun_color = SUNSET_SUN.lerp(DAY_SUN, smoothstep(0.0, TWILIGHT, elevation)) * daylight;
+
+        //Ambient picks up some of the sky's colour so nights read as blue rather than grey
+        let ambient = NIGHT_AMBIENT + (DAY_AMBIENT - NIGHT_AMBIENT) * daylight;
+        let sky_tint = sky_zenith / sky_zenith.max_element();
+        let ambient_color = Vec3::ONE.lerp(sky_tint, 0.3) * ambient;
+
+        Self {
+            sun_direction,
+            sun_color,
+            ambient_color,
+            sky_zenith,
+            sky_horizon,
+            up
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -1,27 +1,63 @@
+use crate::rendering::error::GpuError;
+use crate::rendering::gpu::{self, GpuResource, GpuResourceKind};
+
+pub struct VertexAttribute {
+    //The name of the matching `in` variable in the vertex shader
+    pub name: &'static str,
+    pub location: u32,
+    pub components: i32
+}
+
+//Every attribute is a run of floats, interleaved in the order given
+pub struct VertexLayout {
+    //What the layout is for, e.g. chunk, so errors can say which mesh doesn't fit which shader
+    pub name: &'static str,
+    pub attributes: &'static [VertexAttribute]
+}
+
+impl VertexLayout {
+    pub fn floats_per_vertex(&self) -> usize {
+        self.attributes.iter().map(|a| a.components as usize).sum()
+    }
+
+    //Points the attributes of the bound vertex array at the bound array buffer
+    pub fn enable_attributes(&self) {
+        let stride = (self.floats_per_vertex() * std::mem::size_of::<f32>()) as i32;
+        let mut offset = 0;
+        for attribute in self.attributes {
+            unsafe {
+                gl::VertexAttribPointer(
+                    attribute.location,
+                    attribute.components,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (offset * std::mem::size_of::<f32>()) as *const _
+                );
+                gl::EnableVertexAttribArray(attribute.location);
+            }
+            offset += attribute.components as usize;
+        }
+    }
+}
+
 pub struct Mesh {
-    vao: u32,
-    vbo: u32,
-    ebo: u32,
+    vao: GpuResource,
+    //Never read again, but they must live as long as the vertex array that points into them
+    _vbo: GpuResource,
+    _ebo: GpuResource,
     index_count: i32
 }
 
 impl Mesh {
-    pub fn from_vertices_and_indices(vertices: &[f32], indices: &[u32]) -> Self {
+    pub fn from_vertices_and_indices(vertices: &[f32], indices: &[u32], layout: &VertexLayout) -> Result<Self, GpuError> {
         unsafe {
-            let mut vao = 0;
-            gl::GenVertexArrays(1, &mut vao);
-            assert_ne!(vao, 0);
-
-            //All good? Bind it.
-            gl::BindVertexArray(vao);
+            let vao = GpuResource::new(GpuResourceKind::VertexArray)?;
+            gl::BindVertexArray(vao.id());
 
-            //Create the VBO and ensure it is assigned
-            let mut vbo = 0;
-            gl::GenBuffers(1, &mut vbo);
-            assert_ne!(vbo, 0);
-
-            //All good? Bind it and assign some triangle data.
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            //Create the VBO and assign some triangle data.
+            let vbo = GpuResource::new(GpuResourceKind::Buffer)?;
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo.id());
             gl::BufferData(
                 gl::ARRAY_BUFFER,
                 (vertices.len() * std::mem::size_of::<f32>()) as isize,
@@ -29,9 +65,8 @@ impl Mesh {
                 gl::STATIC_DRAW
             );
 
-            let mut ebo = 0;
-            gl::GenBuffers(1, &mut ebo);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            let ebo = GpuResource::new(GpuResourceKind::Buffer)?;
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo.id());
             gl::BufferData(
                 gl::ELEMENT_ARRAY_BUFFER,
                 (indices.len() * std::mem::size_of::<u32>()) as isize,
@@ -40,39 +75,31 @@ impl Mesh {
             );
 
             //Tell OpenGL what we will be binding
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                std::mem::size_of::<[f32; 3]>() as i32,
-                0 as *const _
-            );
-            gl::EnableVertexAttribArray(0);
+            layout.enable_attributes();
 
-            return Self {
+            return Ok(Self {
                 vao,
-                vbo,
-                ebo,
+                _vbo: vbo,
+                _ebo: ebo,
                 index_count: indices.len() as i32
-            };
+            });
         }
     }
 
     pub fn draw(&self) {
         unsafe {
-            gl::BindVertexArray(self.vao);
+            gl::BindVertexArray(self.vao.id());
             gl::DrawElements(gl::TRIANGLES, self.index_count, gl::UNSIGNED_INT, std::ptr::null());
         }
+        gpu::count_draw_calls(1);
     }
-}
 
-impl Drop for Mesh {
-    fn drop(&mut self) {
+    //For meshes whose indices are pairs of line end points rather than triangles
+    pub fn draw_lines(&self) {
         unsafe {
-            gl::DeleteVertexArrays(1, &self.vao);
-            gl::DeleteBuffers(1, &self.vbo);
-            gl::DeleteBuffers(1, &self.ebo);
+            gl::BindVertexArray(self.vao.id());
+            gl::DrawElements(gl::LINES, self.index_count, gl::UNSIGNED_INT, std::ptr::null());
         }
+        gpu::count_draw_calls(1);
     }
 }
@@ -1,128 +1,590 @@
 mod shaders;
 pub mod camera;
+pub mod debug_view;
+pub mod error;
+pub mod gpu;
+pub mod lighting;
 pub mod mesh;
+pub mod sprite_batch;
 pub mod text;
+pub mod texture;
 pub mod render_context;
+pub mod render_target;
+pub mod shadow;
+#[cfg(test)]
+pub(crate) mod headless;
 
-use crate::RenderContext;
+use render_context::{RenderContext, RenderMesh};
+use render_target::RenderTarget;
+use lighting::Lighting;
 use camera::{Camera, Lens};
+use error::{GpuError, RenderError};
+use gpu::{GpuResource, GpuResourceKind};
+use crate::debug::{draw, profiler};
+use debug_view::{DebugLines, RenderDebugMode, VIEW_FLAT, VIEW_NORMALS, VIEW_OVERDRAW, VIEW_SHADED};
 use gl;
-use glam::Mat4;
+use glam::{Mat4, Vec2, Vec3, Vec4Swizzles};
 use mesh::Mesh;
+use sdl2::pixels::Color;
 use sdl2::video::Window;
 use shaders::Shader;
-use tracing::debug;
+use shadow::ShadowMap;
+use sprite_batch::{Draw2D, SpriteBatch, SPRITE_VERTEX_LAYOUT};
+use text::Fonts;
+use texture::{Image, Texture, TextureArray, TextureRegion};
+use crate::assets::Assets;
+use crate::error::report;
+use crate::world::block::BlockRegistry;
+use crate::world::chunk_mesh_manager::CHUNK_VERTEX_LAYOUT;
+use crate::world::light::MAX_LIGHT;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
 
-pub fn init<'sdl2>(window: &'sdl2 mut Window) -> Renderer<'sdl2> {
+const DEFAULT_SHADOW_RESOLUTION: u32 = 2048;
+//How often shader files are checked for changes
+const SHADER_POLL_INTERVAL: Duration = Duration::from_millis(500);
+pub const SCREENSHOT_DIRECTORY: &str = "screenshots";
+
+//Fails with an error naming whichever shader or asset couldn't be loaded
+pub fn init<'sdl2>(window: &'sdl2 mut Window, blocks: &BlockRegistry, assets: Rc<Assets>) -> Result<Renderer<'sdl2>, RenderError> {
     debug!("Initialising a renderer...");
-    //Create our shaders 
-    //TODO - MAKE THIS SHIT NOT RELATIVE AND BUNDLE WITH RELEASE
-    let shader_result = shaders::create_shader("src/rendering/shaders/default.vert", "src/rendering/shaders/default.frag");
-
-    let shader = match shader_result {
-        Ok(shader) => shader,
-        Err(error) => {
-            println!("This is the error: {}", error);
-            panic!("{error}")
-        },
-    };
-
-    let text_shader_result = shaders::create_shader("src/rendering/shaders/default_text.vert", "src/rendering/shaders/default_text.frag");
-    let text_shader = match text_shader_result {
-        Ok(shader) => shader,
-        Err(error) => {
-            println!("This is the error: {}", error);
-            panic!("{error}")
-        },
-    };
+    //Create our shaders
+    let mut defines = debug_view::shader_defines();
+    defines.push(("MAX_LIGHT".to_string(), MAX_LIGHT.to_string()));
+    let shader = shaders::create_shader_with_defines(&assets, "shaders/default.vert", "shaders/default.frag", defines, &[&CHUNK_VERTEX_LAYOUT, &debug_view::LINE_LAYOUT])?;
+    let sprite_shader = shaders::create_shader(&assets, "shaders/default_2d.vert", "shaders/default_2d.frag", &[&SPRITE_VERTEX_LAYOUT])?;
+    let shadow_shader = shaders::create_shader(&assets, "shaders/shadow.vert", "shaders/shadow.frag", &[&CHUNK_VERTEX_LAYOUT])?;
+    let sky_shader = shaders::create_shader(&assets, "shaders/sky.vert", "shaders/sky.frag", &[])?;
+
+    //The sky triangle is generated in the vertex shader, but core profile still wants a VAO bound
+    let sky_vao = GpuResource::new(GpuResourceKind::VertexArray)?;
+
+    let block_textures = texture::load_block_textures(blocks, &assets)?;
+    let shadow_map = ShadowMap::new(DEFAULT_SHADOW_RESOLUTION)?;
     let lens = Lens::new();
 
-    return Renderer {
+    Ok(Renderer {
         window,
         shader,
-        text_shader,
+        sprite_shader,
+        sky_shader,
+        shadow_shader,
+        sky_vao,
+        sprite_batch: SpriteBatch::new()?,
+        fonts: Fonts::new(),
+        panel_texture: create_panel_texture()?,
+        block_textures,
+        shadow_map,
+        chunk_box: debug_view::chunk_box_mesh()?,
+        debug_lines: DebugLines::new()?,
+        shader_errors: BTreeMap::new(),
+        last_shader_poll: Instant::now(),
         active_lens: lens,
-    }
+        assets,
+        target: None,
+        screenshot_requested: false,
+    })
 }
 
 pub struct Renderer<'sdl2> {
     window: &'sdl2 mut Window,
     pub shader: Shader,
-    pub text_shader: Shader,
+    pub sprite_shader: Shader,
+    pub sky_shader: Shader,
+    pub shadow_shader: Shader,
+    sky_vao: GpuResource,
+    sprite_batch: SpriteBatch,
+    fonts: Fonts,
+    panel_texture: Texture,
+    block_textures: TextureArray,
+    shadow_map: ShadowMap,
+    chunk_box: Mesh,
+    debug_lines: DebugLines,
+    //The latest compile error for each shader that failed to reload, by shader name
+    shader_errors: BTreeMap<String, String>,
+    last_shader_poll: Instant,
     active_lens: Lens,
+    assets: Rc<Assets>,
+    //Where frames are drawn when it isn't the window, such as for screenshots
+    target: Option<RenderTarget>,
+    screenshot_requested: bool,
 }
 
 impl<'a> Renderer<'a> {
-    pub fn render(&mut self, render_context: RenderContext) {
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    pub fn fonts_mut(&mut self) -> &mut Fonts {
+        &mut self.fonts
+    }
+
+    pub fn panel_texture(&self) -> TextureRegion {
+        self.panel_texture.region()
+    }
+
+    pub fn screen_size(&self) -> (f32, f32) {
+        let (width, height) = match &self.target {
+            Some(target) => target.size(),
+            None => self.window.size()
+        };
+        (width as f32, height as f32)
+    }
+
+    //The size in pixels of whatever is being drawn into, the window or an offscreen target
+    pub fn target_size(&self) -> (u32, u32) {
+        match &self.target {
+            Some(target) => target.size(),
+            None => self.window.drawable_size()
+        }
+    }
+
+    fn bind_target(&self) {
+        let (width, height) = self.target_size();
         unsafe {
-            debug!("New frame starting - clearing buffer bit and enabling depth test.");
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.target.as_ref().map_or(0, RenderTarget::fbo));
+            gl::Viewport(0, 0, width as i32, height as i32);
+        }
+    }
+
+    //The next frame is drawn offscreen, saved to the screenshots directory, then shown as usual
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    //Draws a single frame of the given context into an offscreen image of a fixed size, without
+    //touching the window. Works on a headless machine with a software OpenGL such as Mesa's
+    //llvmpipe, e.g. under SDL_VIDEODRIVER=offscreen or xvfb-run with LIBGL_ALWAYS_SOFTWARE=1
+    pub fn render_to_image(&mut self, render_context: RenderContext, size: (u32, u32)) -> Result<Image, RenderError> {
+        let target = self.target.insert(RenderTarget::new(size.0, size.1)?);
+        let fbo = target.fbo();
+        self.begin_frame();
+        self.render(render_context);
+
+        let image = render_target::read_pixels(fbo, size);
+        self.target = None;
+        self.bind_target();
+        Ok(image)
+    }
+
+    pub fn set_mouse_grab(&mut self, grab: bool) {
+        self.window.set_mouse_grab(grab);
+    }
+
+    //Recompiles any shader whose files have changed on disk, keeping the old program if it fails
+    pub fn reload_changed_shaders(&mut self) {
+        if self.last_shader_poll.elapsed() < SHADER_POLL_INTERVAL {
+            return;
+        }
+        self.last_shader_poll = Instant::now();
+
+        for shader in [&mut self.shader, &mut self.sprite_shader, &mut self.sky_shader, &mut self.shadow_shader] {
+            match shader.reload_if_changed(&self.assets) {
+                Some(Ok(())) => {
+                    info!("Reloaded shader {}.", shader.name());
+                    self.shader_errors.remove(&shader.name());
+                }
+                Some(Err(error)) => {
+                    let error = report(&error);
+                    warn!("Failed to reload shader {}, keeping the previous version: {}", shader.name(), error);
+                    self.shader_errors.insert(shader.name(), error);
+                }
+                None => {}
+            }
+        }
+    }
+
+    pub fn shader_errors(&self) -> impl Iterator<Item = &String> {
+        self.shader_errors.values()
+    }
 
-            // Clear and setup
+    pub fn begin_frame(&mut self) {
+        if self.screenshot_requested && self.target.is_none() {
+            let (width, height) = self.window.drawable_size();
+            match RenderTarget::new(width, height) {
+                Ok(target) => self.target = Some(target),
+                Err(error) => {
+                    warn!("Unable to take a screenshot: {}", report(&error));
+                    self.screenshot_requested = false;
+                }
+            }
+        }
+        self.bind_target();
+
+        unsafe {
+            debug!("New frame starting - clearing buffer bit.");
             gl::ClearColor(0.5, 0.5, 1.0, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        let _scope = profiler::scope("present");
+        //Anything submitted when nothing was rendered, like from a menu, shouldn't pile up
+        draw::take();
+        if let Some(target) = self.target.take() {
+            self.screenshot_requested = false;
+            save_screenshot(&target.read_pixels());
+            target.blit_to_window(self.window.drawable_size());
+            self.bind_target();
+        }
+
+        self.window.gl_swap_window();
+        gpu::end_frame_draw_calls();
+    }
+
+    pub fn render(&mut self, render_context: RenderContext) {
+        let (width, height) = self.target_size();
+        self.active_lens.set_aspect_ratio(width as f32 / height.max(1) as f32);
+        let projection_matrix: Mat4 = camera::get_projection_matrix(&self.active_lens);
+        let view_matrix: Mat4 = camera::get_view_matrix(render_context.camera);
+        let lighting = &render_context.lighting;
+
+        self.resize_shadow_map(render_context.shadow_resolution);
+        let light_view_projection = self.shadow_map.light_view_projection(lighting, render_context.camera.position());
+        self.render_shadow_map(light_view_projection, &render_context.meshes);
+
+        self.render_sky(projection_matrix * view_matrix, lighting);
+
+        let world_scope = profiler::scope("world_pass");
+        unsafe {
             gl::Enable(gl::DEPTH_TEST);
 
             // Use 3D shader
             debug!("3D rendering beginning, enabling 3D shader.");
-            gl::UseProgram(self.shader.shader_program_id);
-            let projection_matrix: Mat4 = camera::get_projection_matrix(&self.active_lens);
-            let view_matrix: Mat4 = camera::get_view_matrix(render_context.camera);
+            self.shader.bind();
 
-            let projection_loc = gl::GetUniformLocation(self.shader.shader_program_id, b"projection\0".as_ptr() as *const i8);
-            gl::UniformMatrix4fv(projection_loc, 1, gl::FALSE, projection_matrix.as_ref().as_ptr());
+            self.shader.set_mat4("projection", &projection_matrix);
 
-            let view_loc = gl::GetUniformLocation(self.shader.shader_program_id, b"view\0".as_ptr() as *const i8);
-            gl::UniformMatrix4fv(view_loc, 1, gl::FALSE, view_matrix.as_ref().as_ptr());
+            self.shader.set_mat4("view", &view_matrix);
 
-            debug!("Rendering all meshes...");
-            for render_mesh in render_context.meshes.iter() {
-                let model_loc = gl::GetUniformLocation(self.shader.shader_program_id, b"model\0".as_ptr() as *const i8);
-                gl::UniformMatrix4fv(model_loc, 1, gl::FALSE, render_mesh.model.as_ref().as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.block_textures.id());
+            self.shader.set_i32("block_textures", 0);
 
-                let color_loc = gl::GetUniformLocation(self.shader.shader_program_id, b"color\0".as_ptr() as *const i8);
-                gl::Uniform3f(color_loc, 0.5, 0.0, 0.5);
+            self.shader.set_vec3("sun_direction", lighting.sun_direction);
+            self.shader.set_vec3("sun_color", lighting.sun_color);
+            self.shader.set_vec3("ambient_color", lighting.ambient_color);
 
-                render_mesh.mesh.draw();
+            self.shader.set_mat4("light_view_projection", &light_view_projection);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.shadow_map.depth_texture());
+            self.shader.set_i32("shadow_map", 1);
+            gl::ActiveTexture(gl::TEXTURE0);
 
-                // Optional outline rendering
-                gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-                gl::LineWidth(1.0);
+            self.shader.set_vec3("color", Vec3::ONE);
+
+            let mode = render_context.debug_mode;
+            let view_mode = match mode {
+                RenderDebugMode::Wireframe => VIEW_FLAT,
+                RenderDebugMode::Normals => VIEW_NORMALS,
+                RenderDebugMode::Overdraw => VIEW_OVERDRAW,
+                _ => VIEW_SHADED
+            };
+            self.shader.set_i32("view_mode", view_mode);
+
+            if mode == RenderDebugMode::Overdraw {
+                //Every fragment adds to the colour buffer, with nothing hidden behind anything else
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
                 gl::Disable(gl::DEPTH_TEST);
-                gl::Uniform3f(color_loc, 1.0, 0.0, 1.0);
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::ONE, gl::ONE);
+            }
+            if mode == RenderDebugMode::Wireframe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            }
+
+            debug!("Rendering {} meshes in {} mode with {} block texture layers...", render_context.meshes.len(), mode, self.block_textures.layers());
+            for render_mesh in render_context.meshes.iter() {
+                self.shader.set_mat4("model", &render_mesh.model);
                 render_mesh.mesh.draw();
-                gl::Enable(gl::DEPTH_TEST);
+            }
+
+            if mode == RenderDebugMode::WireframeOverlay {
+                //Pulled slightly towards the camera so the lines win the depth test against their own faces
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                gl::Enable(gl::POLYGON_OFFSET_LINE);
+                gl::PolygonOffset(-1.0, -1.0);
+                self.shader.set_i32("view_mode", VIEW_FLAT);
+                self.shader.set_vec3("color", Vec3::new(1.0, 0.0, 1.0));
+                for render_mesh in render_context.meshes.iter() {
+                    self.shader.set_mat4("model", &render_mesh.model);
+                    render_mesh.mesh.draw();
+                }
+                gl::Disable(gl::POLYGON_OFFSET_LINE);
                 gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+                self.shader.set_i32("view_mode", view_mode);
+                self.shader.set_vec3("color", Vec3::ONE);
+            }
+
+            //Translucent chunks go furthest first so nearer ones blend over them. They still test
+            //against the opaque depth, but don't write it, or they would hide each other
+            let camera_position = render_context.camera.position();
+            let mut translucent_meshes = render_context.translucent_meshes;
+            translucent_meshes.sort_by(|a, b| {
+                let a = a.center.distance_squared(camera_position);
+                let b = b.center.distance_squared(camera_position);
+                b.total_cmp(&a)
+            });
+
+            debug!("Rendering {} translucent meshes...", translucent_meshes.len());
+            if mode != RenderDebugMode::Overdraw {
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            }
+            gl::DepthMask(gl::FALSE);
+            for render_mesh in translucent_meshes {
+                self.shader.set_mat4("model", &render_mesh.model);
+                render_mesh.mesh.draw();
+            }
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            gl::Enable(gl::DEPTH_TEST);
+
+            if mode == RenderDebugMode::ChunkBoundaries {
+                self.shader.set_i32("view_mode", VIEW_FLAT);
+                self.shader.set_vec3("color", Vec3::new(1.0, 1.0, 0.0));
+                for render_mesh in render_context.meshes.iter() {
+                    self.shader.set_mat4("model", &render_mesh.model);
+                    self.chunk_box.draw_lines();
+                }
             }
             debug!("3D rendering finished.");
+        }
+        drop(world_scope);
+
+        let mut overlay = render_context.overlay;
+        overlay.extend(self.render_debug_draws(projection_matrix * view_matrix));
+        self.render_2d(&overlay);
+    }
+
+    //Keeps the old shadow map if a new one can't be made at the requested size
+    fn resize_shadow_map(&mut self, resolution: u32) {
+        if resolution == self.shadow_map.resolution() {
+            return;
+        }
+
+        match ShadowMap::new(resolution) {
+            Ok(shadow_map) => self.shadow_map = shadow_map,
+            Err(error) => warn!("Keeping {}x{} shadow map: {}", self.shadow_map.resolution(), self.shadow_map.resolution(), report(&error)),
+        }
+    }
+
+    fn render_shadow_map(&mut self, light_view_projection: Mat4, meshes: &[&RenderMesh]) {
+        let _scope = profiler::scope("shadow_pass");
+        unsafe {
+            debug!("Rendering {} meshes into the shadow map...", meshes.len());
+            self.shadow_map.begin();
+            gl::Enable(gl::DEPTH_TEST);
+            self.shadow_shader.bind();
 
-            // Switch to 2D text rendering
+            self.shadow_shader.set_mat4("light_view_projection", &light_view_projection);
+
+            //Front faces are culled so the depth stored is the far side of each block, which
+            //keeps lit faces from shadowing themselves
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::FRONT);
+            for render_mesh in meshes {
+                self.shadow_shader.set_mat4("model", &render_mesh.model);
+                render_mesh.mesh.draw();
+            }
+            gl::CullFace(gl::BACK);
+            gl::Disable(gl::CULL_FACE);
+
+            self.bind_target();
+        }
+    }
+
+    fn render_sky(&mut self, view_projection: Mat4, lighting: &Lighting) {
+        let _scope = profiler::scope("sky_pass");
+        unsafe {
+            debug!("Rendering sky...");
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::FALSE);
+            self.sky_shader.bind();
+
+            self.sky_shader.set_mat4("inverse_view_projection", &view_projection.inverse());
+
+            self.sky_shader.set_vec3("sky_zenith", lighting.sky_zenith);
+            self.sky_shader.set_vec3("sky_horizon", lighting.sky_horizon);
+            self.sky_shader.set_vec3("sun_direction", lighting.sun_direction);
+            self.sky_shader.set_vec3("sun_color", lighting.sun_color);
+            self.sky_shader.set_vec3("up", lighting.up);
+
+            gl::BindVertexArray(self.sky_vao.id());
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gpu::count_draw_calls(1);
+            gl::DepthMask(gl::TRUE);
+        }
+    }
+
+    //Draws the lines submitted through debug::draw, and returns its text placed on the screen for
+    //the 2D pass
+    fn render_debug_draws(&mut self, view_projection: Mat4) -> Vec<Draw2D> {
+        let draws = draw::take();
+        if draws.is_empty() {
+            return Vec::new();
+        }
+        let _scope = profiler::scope("debug_draw_pass");
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            self.shader.bind();
+            self.shader.set_i32("view_mode", VIEW_FLAT);
+            self.debug_lines.draw(&self.shader, &draws.lines);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let Some(font) = self.fonts.default_font() else {
+            return Vec::new();
+        };
+        let (width, height) = self.screen_size();
+        draws.texts.into_iter().filter_map(|(position, text, color): (Vec3, String, Color)| {
+            let clip = view_projection * position.extend(1.0);
+            //Behind the camera
+            if clip.w <= 0.0 {
+                return None;
+            }
+            let ndc = clip.xyz() / clip.w;
+            let screen = ((ndc.x * 0.5 + 0.5) * width, (0.5 - ndc.y * 0.5) * height);
+            Some(Draw2D::text(font, text, screen, color).on_layer(1))
+        }).collect()
+    }
+
+    pub fn render_2d(&mut self, draws: &[Draw2D]) {
+        let _scope = profiler::scope("overlay_pass");
+        unsafe {
+            // Switch to 2D rendering
             debug!("2D rendering beginning, enabling 2D shader...");
             gl::Disable(gl::DEPTH_TEST);
-            gl::UseProgram(self.text_shader.shader_program_id);
+            self.sprite_shader.bind();
             gl::Enable(gl::BLEND);
             gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
 
-            let screen_size = [800.0, 600.0];
-            let screen_size_loc = gl::GetUniformLocation(self.text_shader.shader_program_id, b"screen_size\0".as_ptr() as *const i8);
-            gl::Uniform2f(screen_size_loc, screen_size[0], screen_size[1]);
+            let screen_size = self.screen_size();
+            self.sprite_shader.set_vec2("screen_size", Vec2::new(screen_size.0, screen_size.1));
 
-            debug!("Rendering all surfaces...");
-            for surface in render_context.quads.iter() {
-                let texture = &surface.texture;
+            self.sprite_shader.set_i32("sprite_texture", 0);
+
+            debug!("Rendering {} 2D elements...", draws.len());
+            self.sprite_batch.draw(&self.fonts, screen_size, draws);
+            debug!("2D rendering finished.");
+        }
+    }
+}
 
-                let screen_pos_loc = gl::GetUniformLocation(self.text_shader.shader_program_id, b"screen_pos\0".as_ptr() as *const i8);
-                let scale_loc = gl::GetUniformLocation(self.text_shader.shader_program_id, b"scale\0".as_ptr() as *const i8);
-                let sampler_loc = gl::GetUniformLocation(self.text_shader.shader_program_id, b"text_texture\0".as_ptr() as *const i8);
+//Named by the time it was taken, so screenshots sort in the order they were taken
+fn save_screenshot(image: &Image) {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let path = Path::new(SCREENSHOT_DIRECTORY).join(format!("screenshot_{}.png", millis));
+    match image.save_png(&path) {
+        Ok(()) => info!("Saved screenshot to {}.", path.display()),
+        Err(error) => warn!("Unable to save screenshot: {}", report(&error))
+    }
+}
 
-                gl::Uniform2f(screen_pos_loc, 0.0, 0.0);
-                gl::Uniform2f(scale_loc, texture.width as f32, texture.height as f32);
-                gl::Uniform1i(sampler_loc, 0);
+//A small bordered square for menus and HUD panels, meant to be drawn as a nine-slice with a border of 2
+fn create_panel_texture() -> Result<Texture, GpuError> {
+    const SIZE: u32 = 8;
+    const BORDER: u32 = 2;
 
-                surface.quad.draw_with_texture(texture.texture_id);
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let edge = x < BORDER || y < BORDER || x >= SIZE - BORDER || y >= SIZE - BORDER;
+            if edge {
+                pixels.extend_from_slice(&[200, 200, 220, 255]);
+            } else {
+                pixels.extend_from_slice(&[20, 20, 30, 200]);
             }
-            debug!("2D rendering finished.");
         }
+    }
 
-        self.window.gl_swap_window();
+    Texture::from_rgba(SIZE, SIZE, &pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use headless::HeadlessContext;
+    use crate::events::{Event, EventHandler};
+    use crate::world::ChunkMap;
+    use crate::world::block::{AIR, GLASS, GRASS, LAMP, STONE, WATER};
+    use crate::world::chunk::{Chunk, CHUNK_SIZE};
+    use crate::world::chunk_mesh_manager::ChunkMeshManager;
+    use crate::world::light::LightMap;
+    use crate::world::time::WorldClock;
+    use std::env;
+    use std::fs;
+
+    const GOLDEN: &str = "tests/golden/scene.png";
+    //Set to write the current render over the reference image instead of comparing against it
+    const BLESS_ENVIRONMENT_VARIABLE: &str = "KARDASHEV_BLESS";
+    const SIZE: (u32, u32) = (160, 120);
+    //Software and hardware OpenGL round slightly differently, so only count clearly wrong pixels
+    const TOLERANCE: u8 = 8;
+    const MAX_DIFFERING_PIXELS: usize = 64;
+
+    //A grass platform with a lamp, a pool of water and a glass block, seen from above one corner
+    //in the morning, so shadows, block light and both mesh passes all show up
+    fn scene_chunk() -> Chunk {
+        let mut blocks = [[[AIR; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        for slice in blocks.iter_mut().take(8) {
+            slice[0][..8].fill(STONE);
+            slice[1][..8].fill(GRASS);
+        }
+        blocks[2][1][5] = WATER;
+        blocks[3][1][5] = WATER;
+        blocks[5][2][2] = LAMP;
+        blocks[2][2][2] = GLASS;
+        blocks[6][2][6] = STONE;
+        blocks[6][3][6] = STONE;
+        Chunk { blocks }
+    }
+
+    #[test]
+    #[ignore = "needs a display with OpenGL 3.3"]
+    fn scene_matches_golden_image() {
+        let mut context = HeadlessContext::new().expect("needs a display with OpenGL 3.3");
+        let blocks = Rc::new(BlockRegistry::new());
+        let mut renderer = init(&mut context.window, &blocks, Rc::new(Assets::new())).unwrap();
+
+        let mut chunks = ChunkMap::new();
+        chunks.insert((0, 0, 0), scene_chunk());
+        let mut light = LightMap::new();
+        light.light_chunk(&chunks, (0, 0, 0));
+        let mut meshes = ChunkMeshManager::new(blocks.clone());
+        meshes.on_event(&Event::ChunkLoaded((0, 0, 0), chunks[&(0, 0, 0)].blocks, light.padded((0, 0, 0))));
+
+        let camera = Camera::with_pose(Vec3::new(-0.4, 0.7, -0.4), std::f32::consts::FRAC_PI_4, -0.5);
+        let render_context = RenderContext {
+            camera: &camera,
+            lighting: Lighting::from_clock(&WorldClock::at(0.35), Vec3::Y),
+            meshes: meshes.meshes(),
+            translucent_meshes: meshes.translucent_meshes(),
+            shadow_resolution: 512,
+            debug_mode: RenderDebugMode::Off,
+            overlay: Vec::new()
+        };
+        let image = renderer.render_to_image(render_context, SIZE).unwrap();
+
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN);
+        if env::var_os(BLESS_ENVIRONMENT_VARIABLE).is_some() {
+            image.save_png(&golden).unwrap();
+            return;
+        }
+        let expected = Image::decode_png(GOLDEN, &fs::read(&golden).unwrap()).unwrap();
+        let differing = image.differing_pixels(&expected, TOLERANCE);
+        if differing > MAX_DIFFERING_PIXELS {
+            //Kept next to the build output so the two can be compared by eye
+            let actual = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden/scene.png");
+            image.save_png(&actual).unwrap();
+            panic!("{} pixels differ from {}, the render was saved to {}", differing, GOLDEN, actual.display());
+        }
     }
 }
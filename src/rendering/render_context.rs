@@ -1,15 +1,24 @@
-use glam::Mat4;
+use glam::{Mat4, Vec3};
 use crate::rendering::Camera;
 use crate::rendering::Mesh;
-use crate::rendering::text::Surface2D;
+use crate::rendering::debug_view::RenderDebugMode;
+use crate::rendering::lighting::Lighting;
+use crate::rendering::sprite_batch::Draw2D;
 
 pub struct RenderMesh {
     pub mesh: Mesh,
-    pub model: Mat4
+    pub model: Mat4,
+    //World space centre, used to sort translucent meshes back to front
+    pub center: Vec3
 }
 
 pub struct RenderContext<'frame> {
     pub camera: &'frame Camera,
+    pub lighting: Lighting,
     pub meshes: Vec<&'frame RenderMesh>,
-    pub quads: Vec<&'frame Surface2D>
+    pub translucent_meshes: Vec<&'frame RenderMesh>,
+    //Width and height of the sun's shadow map in texels
+    pub shadow_resolution: u32,
+    pub debug_mode: RenderDebugMode,
+    pub overlay: Vec<Draw2D>
 }
@@ -0,0 +1,101 @@
+use tracing::debug;
+use crate::rendering::error::RenderError;
+use crate::rendering::gpu::{GpuResource, GpuResourceKind};
+use crate::rendering::texture::Image;
+
+//An offscreen colour and depth buffer to draw a frame into instead of the window, so it can be
+//read back as an image
+pub struct RenderTarget {
+    fbo: GpuResource,
+    _color: GpuResource,
+    _depth: GpuResource,
+    width: u32,
+    height: u32
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32) -> Result<Self, RenderError> {
+        debug!("Creating a {}x{} render target...", width, height);
+        let color = GpuResource::new(GpuResourceKind::Texture)?;
+        let depth = GpuResource::new(GpuResourceKind::Texture)?;
+        let fbo = GpuResource::new(GpuResourceKind::Framebuffer)?;
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, color.id());
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA8 as i32, width as i32, height as i32, 0, gl::RGBA, gl::UNSIGNED_BYTE, std::ptr::null());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+            gl::BindTexture(gl::TEXTURE_2D, depth.id());
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::DEPTH_COMPONENT24 as i32, width as i32, height as i32, 0, gl::DEPTH_COMPONENT, gl::FLOAT, std::ptr::null());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo.id());
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, color.id(), 0);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, depth.id(), 0);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(RenderError::IncompleteRenderTarget { status });
+            }
+        }
+
+        Ok(Self {
+            fbo,
+            _color: color,
+            _depth: depth,
+            width,
+            height
+        })
+    }
+
+    pub fn fbo(&self) -> u32 {
+        self.fbo.id()
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn read_pixels(&self) -> Image {
+        read_pixels(self.fbo.id(), self.size())
+    }
+
+    //Copies the frame onto the window, for when it should be seen as well as captured
+    pub fn blit_to_window(&self, window_size: (u32, u32)) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo.id());
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0, 0, self.width as i32, self.height as i32,
+                0, 0, window_size.0 as i32, window_size.1 as i32,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+}
+
+//OpenGL counts rows from the bottom, so they are flipped to give a top down image. Alpha is
+//forced to opaque, since what was blended into the framebuffer's alpha isn't meaningful
+pub fn read_pixels(fbo: u32, (width, height): (u32, u32)) -> Image {
+    let row_bytes = (width * 4) as usize;
+    let mut buffer = vec![0u8; row_bytes * height as usize];
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, fbo);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE, buffer.as_mut_ptr().cast());
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+    }
+
+    let mut pixels = Vec::with_capacity(buffer.len());
+    for row in buffer.chunks(row_bytes).rev() {
+        pixels.extend(row.chunks(4).flat_map(|p| [p[0], p[1], p[2], 255]));
+    }
+
+    Image { width, height, pixels }
+}
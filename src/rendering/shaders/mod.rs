@@ -1,8 +1,18 @@
+mod preprocess;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::LazyLock;
 use gl::types::{GLenum, GLuint};
-use std::fs::read_to_string;
+use glam::{Mat4, Vec2, Vec3};
 use regex::Regex;
-use tracing::debug;
+use tracing::{debug, warn};
+use preprocess::{preprocess, PreprocessedSource, ShaderSource};
+use crate::assets::Assets;
+use crate::rendering::error::ShaderError;
+use crate::rendering::gpu::{GpuResource, GpuResourceKind};
+use crate::rendering::mesh::VertexLayout;
 
 //TODO
 //The idea is you create a shader and get a mutable reference to it
@@ -12,9 +22,96 @@ use tracing::debug;
 //which abstracts away all the OpenGL calls
 
 pub struct Shader {
-    pub shader_program_id: GLuint,
-    pub vertex_shader_id: GLuint,
-    pub fragment_shader_id: GLuint
+    program: GpuResource,
+    //Active uniforms and attributes by name, read back from the program once it has linked
+    uniforms: HashMap<String, i32>,
+    attributes: HashMap<String, u32>,
+    //Names we have already warned about, so a bad name in a render loop only logs once
+    warned: RefCell<HashSet<String>>,
+    source: ShaderSource
+}
+
+impl Shader {
+    //The vertex shader's file name without its extension, e.g. default or sky
+    pub fn name(&self) -> String {
+        Path::new(&self.source.vertex).file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string())
+    }
+
+    //Rebuilds the program if any file it was made from has changed. On failure the old program
+    //is kept, so a typo in a shader doesn't take the renderer down with it, but the files it now
+    //includes are still watched so fixing any of them triggers another reload
+    pub fn reload_if_changed(&mut self, assets: &Assets) -> Option<Result<(), ShaderError>> {
+        if !self.source.poll_changed() {
+            return None;
+        }
+
+        debug!("Reloading shader {}...", self.name());
+        match compile_shader(assets, &mut self.source) {
+            Ok(shader) => {
+                *self = shader;
+                Some(Ok(()))
+            }
+            Err(error) => Some(Err(error))
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::UseProgram(self.program.id());
+        }
+    }
+
+    //None if the program has no active uniform by this name, either because it was never declared
+    //or because the compiler optimised it away
+    pub fn uniform_location(&self, name: &str) -> Option<i32> {
+        let location = self.uniforms.get(name).copied();
+        if location.is_none() && self.warned.borrow_mut().insert(name.to_string()) {
+            warn!("Shader program {} has no active uniform named {}.", self.program.id(), name);
+        }
+        location
+    }
+
+    pub fn attribute_location(&self, name: &str) -> Option<u32> {
+        self.attributes.get(name).copied()
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.attributes.iter().map(|(name, &location)| (name.as_str(), location))
+    }
+
+    //The setters write to the currently bound program, so bind this one first
+    pub fn set_mat4(&self, name: &str, value: &Mat4) {
+        if let Some(location) = self.uniform_location(name) {
+            unsafe {
+                gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ref().as_ptr());
+            }
+        }
+    }
+
+    pub fn set_vec2(&self, name: &str, value: Vec2) {
+        if let Some(location) = self.uniform_location(name) {
+            unsafe {
+                gl::Uniform2f(location, value.x, value.y);
+            }
+        }
+    }
+
+    pub fn set_vec3(&self, name: &str, value: Vec3) {
+        if let Some(location) = self.uniform_location(name) {
+            unsafe {
+                gl::Uniform3f(location, value.x, value.y, value.z);
+            }
+        }
+    }
+
+    //Also used for booleans and sampler texture units
+    pub fn set_i32(&self, name: &str, value: i32) {
+        if let Some(location) = self.uniform_location(name) {
+            unsafe {
+                gl::Uniform1i(location, value);
+            }
+        }
+    }
 }
 
 fn initialise_shader(shader_code: &str, shader_type: GLenum) -> Result<GLuint, String> {
@@ -56,6 +153,7 @@ fn initialise_shader(shader_code: &str, shader_type: GLenum) -> Result<GLuint, S
           );
           v.set_len(log_len.try_into().unwrap());
           debug!("Shader failed to compile.");
+          gl::DeleteShader(shader);
           return Err(String::from_utf8_lossy(&v).to_string());
       }
 
@@ -65,35 +163,69 @@ fn initialise_shader(shader_code: &str, shader_type: GLenum) -> Result<GLuint, S
   }
 }
 
-pub fn create_shader<T: AsRef<Path>>(vertex_shader_path: T, fragment_shader_path: T) -> Result<Shader, String> {
+//Shaders are assets, named like shaders/sky.vert. Layouts are the vertex formats of the meshes the
+//shader will draw, and loading fails if the vertex shader's inputs don't line up with them
+pub fn create_shader(assets: &Assets, vertex_shader: &str, fragment_shader: &str, layouts: &[&'static VertexLayout]) -> Result<Shader, ShaderError> {
+    create_shader_with_defines(assets, vertex_shader, fragment_shader, Vec::new(), layouts)
+}
+
+//Defines are added to both stages, so constants shared with Rust only need writing down once
+pub fn create_shader_with_defines(assets: &Assets, vertex_shader: &str, fragment_shader: &str, defines: Vec<(String, String)>, layouts: &[&'static VertexLayout]) -> Result<Shader, ShaderError> {
+    compile_shader(assets, &mut ShaderSource::new(vertex_shader, fragment_shader, defines, layouts))
+}
+
+//Names the files behind the numbers in #line directives, since compile logs only give the number
+fn describe_error(name: &str, source: &PreprocessedSource, log: String) -> ShaderError {
+    let mut log = log.trim_end().to_string();
+    if source.files.len() > 1 {
+        for (index, file) in source.files.iter().enumerate() {
+            log.push_str(&format!("\n  file {} is {}", index, file));
+        }
+    }
+    ShaderError::Compile { name: name.to_string(), log }
+}
+
+//Updates the source's watch list with every file read, whether or not it compiles
+fn compile_shader(assets: &Assets, source: &mut ShaderSource) -> Result<Shader, ShaderError> {
     unsafe {
-        let vertex_shader_code = read_to_string(vertex_shader_path.as_ref()).expect("Unable to read vertex shader file");
-        let attribute_bindings = extract_attribute_bindings(&vertex_shader_code);
+        let vertex_source = preprocess(assets, &source.vertex, &source.defines)?;
+        let fragment_source = preprocess(assets, &source.fragment, &source.defines)?;
+
+        //Watch what we have read even if it fails to compile, so fixing it triggers a reload
+        let files: Vec<String> = vertex_source.files.iter().chain(fragment_source.files.iter()).cloned().collect();
+        source.watch(assets, &files);
+
+        let vertex_shader_code = vertex_source.code.as_str();
+        let inputs = extract_attribute_bindings(&source.vertex, vertex_shader_code)?;
+        check_inputs_against_layouts(&source.vertex, &inputs, &source.layouts)?;
 
         //First try to initialise our shaders
-        let vertex_shader = initialise_shader(&vertex_shader_code, 0x8B31);
+        let vertex_shader = initialise_shader(vertex_shader_code, 0x8B31);
 
         let vertex_shader_id = match vertex_shader {
             Ok(id) => id,
-            Err(error_string) => return Err(error_string),
+            Err(error_string) => return Err(describe_error(&source.vertex, &vertex_source, error_string)),
         };
 
-        let fragment_shader_code = read_to_string(fragment_shader_path.as_ref()).expect("Unable to read fragment shader file");
-        let fragment_shader = initialise_shader(&fragment_shader_code, 0x8B30);
+        let fragment_shader = initialise_shader(&fragment_source.code, 0x8B30);
 
         let fragment_shader_id = match fragment_shader {
             Ok(id) => id,
-            Err(error_string) => return Err(error_string),
+            Err(error_string) => {
+                gl::DeleteShader(vertex_shader_id);
+                return Err(describe_error(&source.fragment, &fragment_source, error_string));
+            }
         };
 
         debug!("Attempting to create a shader program with OpenGL...");
         
         //Create a shader program and save
-        let shader_program_id = gl::CreateProgram();
-
-        if shader_program_id == 0 {
-            return Err("An error occurred while making the shader program, debug code for this is TODO.".to_string());
-        }
+        let Ok(program) = GpuResource::new(GpuResourceKind::Program) else {
+            gl::DeleteShader(vertex_shader_id);
+            gl::DeleteShader(fragment_shader_id);
+            return Err(ShaderError::CreateProgram { name: source.vertex.clone() });
+        };
+        let shader_program_id = program.id();
 
         debug!("Shader program created, with program ID {}, vertex shader id {}, and fragment shader id {}", shader_program_id, vertex_shader_id, fragment_shader_id);
         debug!("Attempting to attach shaders to program...");
@@ -102,15 +234,6 @@ pub fn create_shader<T: AsRef<Path>>(vertex_shader_path: T, fragment_shader_path
         gl::AttachShader(shader_program_id, vertex_shader_id);
         gl::AttachShader(shader_program_id, fragment_shader_id);
 
-        for (name, loc) in &attribute_bindings {
-            debug!("{}", name);
-            //gl::BindAttribLocation(
-                //shader_program_id,
-                //*loc,
-                //format!("{}/0", name).as_ptr() as *const i8
-            //);
-        }
-
         gl::LinkProgram(shader_program_id);
         
         debug!("Shaders attached.");
@@ -129,7 +252,9 @@ pub fn create_shader<T: AsRef<Path>>(vertex_shader_path: T, fragment_shader_path
           v.set_len(log_len.try_into().unwrap());
           debug!("Link success: {}", success);
           debug!("Log length: {}", log_len);
-          return Err(String::from_utf8_lossy(&v).to_string());
+          gl::DeleteShader(vertex_shader_id);
+          gl::DeleteShader(fragment_shader_id);
+          return Err(ShaderError::Link { name: source.vertex.clone(), log: String::from_utf8_lossy(&v).trim_end().to_string() });
     }
 
     debug!("Shader program compiled successfully.");
@@ -143,22 +268,221 @@ pub fn create_shader<T: AsRef<Path>>(vertex_shader_path: T, fragment_shader_path
 
     debug!("Shader program now being used.");
 
+    let uniforms = introspect_uniforms(shader_program_id);
+    let attributes = introspect_attributes(shader_program_id);
+    debug!("Shader program {} has {} active uniforms and {} active attributes.", shader_program_id, uniforms.len(), attributes.len());
+
+    //Inputs we didn't find a layout qualifier for get whatever location the driver picks, which
+    //the layouts can't be checked against
+    for (name, &location) in &attributes {
+        if !inputs.iter().any(|input| input.name == *name && input.location == location) {
+            return Err(ShaderError::UnqualifiedInput { name: source.vertex.clone(), input: name.clone(), location });
+        }
+    }
+
     return Ok(Shader {
-        shader_program_id,
-        vertex_shader_id,
-        fragment_shader_id
+        program,
+        uniforms,
+        attributes,
+        warned: RefCell::new(HashSet::new()),
+        source: source.clone()
     });
     }
 }
 
-fn extract_attribute_bindings(shader_code: &str) -> Vec<(&str, u32)> {
-    let re = Regex::new(r"layout\s*\(\s*location\s*=\s*(\d+)\s*\)\s*in\s+\w+\s+(\w+)\s*;").unwrap();
-    
-    re.captures_iter(shader_code)
+//Array uniforms are reported as name[0], but are set by their plain name
+fn introspect_uniforms(program: GLuint) -> HashMap<String, i32> {
+    let mut uniforms = HashMap::new();
+    unsafe {
+        let mut count = 0;
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+
+        for index in 0..count as GLuint {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let mut length = 0;
+            let mut size = 0;
+            let mut kind = 0;
+            gl::GetActiveUniform(program, index, max_length, &mut length, &mut size, &mut kind, name.as_mut_ptr().cast());
+            name.truncate(length as usize);
+            name.push(0);
+
+            let location = gl::GetUniformLocation(program, name.as_ptr().cast());
+            name.pop();
+            let name = String::from_utf8_lossy(&name).trim_end_matches("[0]").to_string();
+            debug!("Uniform {} at location {}.", name, location);
+            uniforms.insert(name, location);
+        }
+    }
+    uniforms
+}
+
+fn introspect_attributes(program: GLuint) -> HashMap<String, u32> {
+    let mut attributes = HashMap::new();
+    unsafe {
+        let mut count = 0;
+        let mut max_length = 0;
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_length);
+
+        for index in 0..count as GLuint {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let mut length = 0;
+            let mut size = 0;
+            let mut kind = 0;
+            gl::GetActiveAttrib(program, index, max_length, &mut length, &mut size, &mut kind, name.as_mut_ptr().cast());
+            name.truncate(length as usize);
+            name.push(0);
+
+            //Built in inputs like gl_VertexID are active but have no location
+            let location = gl::GetAttribLocation(program, name.as_ptr().cast());
+            name.pop();
+            if location >= 0 {
+                let name = String::from_utf8_lossy(&name).to_string();
+                debug!("Attribute {} at location {}.", name, location);
+                attributes.insert(name, location as u32);
+            }
+        }
+    }
+    attributes
+}
+
+//A vertex shader input declared like `layout (location = 0) in vec3 pos;`
+struct ShaderInput {
+    name: String,
+    location: u32,
+    components: i32
+}
+
+//Compiled once, since every shader build and hot reload runs it
+static INPUT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"layout\s*\(\s*location\s*=\s*(\d+)\s*\)\s*in\s+(\w+)\s+(\w+)\s*;").expect("Input pattern is a valid regex")
+});
+
+fn extract_attribute_bindings(shader_name: &str, shader_code: &str) -> Result<Vec<ShaderInput>, ShaderError> {
+    INPUT_PATTERN.captures_iter(shader_code)
         .map(|cap| {
-            let location = cap[1].parse::<u32>().unwrap();
-            let name = cap.get(2).unwrap().as_str();
-            (name, location)
+            let name = cap[3].to_string();
+            //Shaders can come from mods, so a location too big to parse is an error rather than a panic
+            let location = cap[1].parse::<u32>()
+                .map_err(|_| ShaderError::InvalidLocation { name: shader_name.to_string(), input: name.clone(), location: cap[1].to_string() })?;
+            //Layouts are runs of floats handed to glVertexAttribPointer, so only float scalars and
+            //vectors can be fed from one. Integer inputs would read the floats' bits as garbage
+            let components = match &cap[2] {
+                "float" => 1,
+                kind => kind.strip_prefix("vec").and_then(|size| size.parse().ok()).filter(|size| (2..=4).contains(size))
+                    .ok_or_else(|| ShaderError::UnsupportedInput { name: shader_name.to_string(), input: name.clone(), kind: kind.to_string() })?
+            };
+            Ok(ShaderInput { name, location, components })
         })
         .collect()
 }
+
+//Every input must come from at least one layout, and any layout that supplies an input by name or
+//by location must agree with the shader on both, and on its size. Layouts may leave inputs out, in
+//which case their arrays are disabled and they read the generic attribute default of (0, 0, 0, 1)
+fn check_inputs_against_layouts(shader_name: &str, inputs: &[ShaderInput], layouts: &[&'static VertexLayout]) -> Result<(), ShaderError> {
+    for input in inputs {
+        let mut supplied = false;
+        for layout in layouts {
+            for attribute in layout.attributes.iter().filter(|attribute| attribute.name == input.name || attribute.location == input.location) {
+                if attribute.name != input.name || attribute.location != input.location || attribute.components != input.components {
+                    return Err(ShaderError::LayoutMismatch {
+                        name: shader_name.to_string(),
+                        input: input.name.clone(),
+                        components: input.components,
+                        location: input.location,
+                        layout: layout.name,
+                        attribute: attribute.name,
+                        attribute_components: attribute.components,
+                        attribute_location: attribute.location
+                    });
+                }
+                supplied = true;
+            }
+        }
+
+        if !supplied {
+            return Err(ShaderError::UnsuppliedInput {
+                name: shader_name.to_string(),
+                input: input.name.clone(),
+                location: input.location,
+                layouts: layouts.iter().map(|layout| layout.name).collect()
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::mesh::VertexAttribute;
+
+    const LAYOUT: VertexLayout = VertexLayout {
+        name: "test",
+        attributes: &[
+            VertexAttribute { name: "pos", location: 0, components: 3 },
+            VertexAttribute { name: "uv", location: 1, components: 2 },
+        ]
+    };
+    const POS_ONLY: VertexLayout = VertexLayout {
+        name: "pos_only",
+        attributes: &[
+            VertexAttribute { name: "pos", location: 0, components: 3 },
+        ]
+    };
+
+    fn check(source: &str, layouts: &[&'static VertexLayout]) -> Result<(), ShaderError> {
+        let inputs = extract_attribute_bindings("test.vert", source)?;
+        check_inputs_against_layouts("test.vert", &inputs, layouts)
+    }
+
+    #[test]
+    fn inputs_are_read_from_layout_qualifiers() {
+        let inputs = extract_attribute_bindings("test.vert", "layout (location = 0) in vec3 pos;\nlayout(location=4)in float light ;\nin vec2 unqualified;").unwrap();
+        let inputs: Vec<(&str, u32, i32)> = inputs.iter().map(|input| (input.name.as_str(), input.location, input.components)).collect();
+        assert_eq!(inputs, [("pos", 0, 3), ("light", 4, 1)]);
+    }
+
+    #[test]
+    fn matching_layout_passes() {
+        assert!(check("layout (location = 0) in vec3 pos;\nlayout (location = 1) in vec2 uv;", &[&LAYOUT]).is_ok());
+        //A second layout may leave inputs out, as long as one of them supplies each input
+        assert!(check("layout (location = 0) in vec3 pos;\nlayout (location = 1) in vec2 uv;", &[&LAYOUT, &POS_ONLY]).is_ok());
+    }
+
+    #[test]
+    fn wrong_location_is_a_mismatch() {
+        let error = check("layout (location = 2) in vec2 uv;", &[&LAYOUT]).unwrap_err();
+        assert!(matches!(error, ShaderError::LayoutMismatch { location: 2, attribute_location: 1, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn wrong_component_count_is_a_mismatch() {
+        let error = check("layout (location = 0) in vec4 pos;", &[&LAYOUT]).unwrap_err();
+        assert!(matches!(error, ShaderError::LayoutMismatch { components: 4, attribute_components: 3, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn unsupplied_input_is_an_error() {
+        let error = check("layout (location = 5) in float light;", &[&LAYOUT, &POS_ONLY]).unwrap_err();
+        assert!(matches!(&error, ShaderError::UnsuppliedInput { location: 5, layouts, .. } if *layouts == ["test", "pos_only"]), "{:?}", error);
+    }
+
+    #[test]
+    fn out_of_range_location_is_an_error() {
+        let error = extract_attribute_bindings("test.vert", "layout (location = 99999999999) in vec3 pos;").err().unwrap();
+        assert!(matches!(&error, ShaderError::InvalidLocation { location, .. } if location == "99999999999"), "{:?}", error);
+    }
+
+    #[test]
+    fn integer_and_matrix_inputs_are_unsupported() {
+        for kind in ["int", "uint", "ivec3", "uvec2", "mat4", "vec5"] {
+            let source = format!("layout (location = 0) in {} pos;", kind);
+            let error = extract_attribute_bindings("test.vert", &source).err().unwrap();
+            assert!(matches!(&error, ShaderError::UnsupportedInput { kind: found, .. } if found == kind), "{:?}", error);
+        }
+    }
+}
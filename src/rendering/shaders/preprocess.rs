@@ -0,0 +1,132 @@
+use std::fs::metadata;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::debug;
+use crate::assets::Assets;
+use crate::rendering::error::ShaderError;
+use crate::rendering::mesh::VertexLayout;
+
+//Shader source after includes have been pasted in and defines added, along with the name of every
+//asset it was built from so they can be watched for changes
+pub struct PreprocessedSource {
+    pub code: String,
+    pub files: Vec<String>
+}
+
+//Replaces `#include "file"` lines with the contents of that file, relative to the including file,
+//and adds a `#define NAME VALUE` line for every define straight after `#version`. `#line`
+//directives keep compile errors pointing at the right line, with the file given by its index
+//into `files`
+pub fn preprocess(assets: &Assets, name: &str, defines: &[(String, String)]) -> Result<PreprocessedSource, ShaderError> {
+    let mut source = PreprocessedSource {
+        code: String::new(),
+        files: Vec::new()
+    };
+    include_file(assets, name, defines, &mut Vec::new(), &mut source)?;
+    Ok(source)
+}
+
+fn include_file(assets: &Assets, name: &str, defines: &[(String, String)], stack: &mut Vec<String>, source: &mut PreprocessedSource) -> Result<(), ShaderError> {
+    if stack.iter().any(|included| included == name) {
+        return Err(ShaderError::IncludeCycle(format!("{} -> {}", stack.join(" -> "), name)));
+    }
+
+    debug!("Preprocessing shader {}...", name);
+    let code = assets.read_to_string(name)?;
+    let index = source.files.len();
+    source.files.push(name.to_string());
+
+    //Included files have no #version line of their own, so start their line numbering straight away
+    if !stack.is_empty() {
+        source.code.push_str(&format!("#line 1 {}\n", index));
+    }
+    stack.push(name.to_string());
+
+    for (number, line) in code.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("#version") {
+            source.code.push_str(line);
+            source.code.push('\n');
+            for (define, value) in defines {
+                source.code.push_str(&format!("#define {} {}\n", define, value));
+            }
+            source.code.push_str(&format!("#line {} {}\n", number + 2, index));
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("#include") {
+            let include = rest.trim().trim_matches('"');
+            if include.is_empty() {
+                return Err(ShaderError::MissingIncludeName { file: name.to_string(), line: number + 1 });
+            }
+
+            let include_name = match name.rsplit_once('/') {
+                Some((directory, _)) => format!("{}/{}", directory, include),
+                None => include.to_string()
+            };
+            include_file(assets, &include_name, &[], stack, source)?;
+            source.code.push_str(&format!("#line {} {}\n", number + 2, index));
+            continue;
+        }
+
+        source.code.push_str(line);
+        source.code.push('\n');
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+//Where a shader came from, so it can be rebuilt when any of its files change on disk. Built in
+//shaders with nothing on disk are never reloaded
+#[derive(Clone)]
+pub struct ShaderSource {
+    pub vertex: String,
+    pub fragment: String,
+    pub defines: Vec<(String, String)>,
+    //Every vertex layout the shader draws, checked against its inputs whenever it is built
+    pub layouts: Vec<&'static VertexLayout>,
+    watched: Vec<(PathBuf, Option<SystemTime>)>
+}
+
+impl ShaderSource {
+    pub fn new(vertex: &str, fragment: &str, defines: Vec<(String, String)>, layouts: &[&'static VertexLayout]) -> Self {
+        Self {
+            vertex: vertex.to_string(),
+            fragment: fragment.to_string(),
+            defines,
+            layouts: layouts.to_vec(),
+            watched: Vec::new()
+        }
+    }
+
+    //Remembers the current modification time of every file, so only later edits count as changes
+    pub fn watch(&mut self, assets: &Assets, files: &[String]) {
+        self.watched.clear();
+        for path in files.iter().filter_map(|file| assets.path(file)) {
+            if !self.watched.iter().any(|(watched, _)| *watched == path) {
+                let modified = modified(&path);
+                self.watched.push((path, modified));
+            }
+        }
+    }
+
+    //Returns true once per change
+    pub fn poll_changed(&mut self) -> bool {
+        let mut changed = false;
+        for (file, last_modified) in &mut self.watched {
+            let current = modified(file);
+            if current != *last_modified {
+                debug!("Shader file {} has changed.", file.display());
+                *last_modified = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
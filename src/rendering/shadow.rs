@@ -0,0 +1,103 @@
+use glam::{Mat4, Vec3, Vec4Swizzles};
+use tracing::debug;
+use crate::rendering::error::RenderError;
+use crate::rendering::gpu::{GpuResource, GpuResourceKind};
+use crate::rendering::lighting::Lighting;
+
+//Half the width of the square the shadow map covers, enough for every chunk around the camera
+const SHADOW_RADIUS: f32 = 6.0;
+//How far back along the sun direction the shadow camera sits, and so how deep its view is
+const SHADOW_DEPTH: f32 = 12.0;
+
+//A depth texture rendered from the sun's point of view, and the framebuffer that draws into it
+pub struct ShadowMap {
+    fbo: GpuResource,
+    depth_texture: GpuResource,
+    resolution: u32
+}
+
+impl ShadowMap {
+    pub fn new(resolution: u32) -> Result<Self, RenderError> {
+        debug!("Creating a {}x{} shadow map...", resolution, resolution);
+        let depth_texture = GpuResource::new(GpuResourceKind::Texture)?;
+        let fbo = GpuResource::new(GpuResourceKind::Framebuffer)?;
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, depth_texture.id());
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH_COMPONENT24 as i32,
+                resolution as i32,
+                resolution as i32,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                std::ptr::null()
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+            //Anything outside the map counts as lit
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+            let border = [1.0f32; 4];
+            gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+
+            //Sampled as a sampler2DShadow, so the hardware does the depth comparison for us
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo.id());
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, depth_texture.id(), 0);
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(RenderError::IncompleteShadowMap { status });
+            }
+        }
+
+        Ok(Self {
+            fbo,
+            depth_texture,
+            resolution
+        })
+    }
+
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    pub fn depth_texture(&self) -> u32 {
+        self.depth_texture.id()
+    }
+
+    //An orthographic view down the sun direction, centred on the camera. The centre is snapped to
+    //whole shadow map texels so shadow edges don't crawl as the camera moves
+    pub fn light_view_projection(&self, lighting: &Lighting, center: Vec3) -> Mat4 {
+        let sun = lighting.sun_direction.normalize();
+        let up = if sun.dot(lighting.up).abs() > 0.99 { sun.any_orthonormal_vector() } else { lighting.up };
+
+        let projection = Mat4::orthographic_rh_gl(-SHADOW_RADIUS, SHADOW_RADIUS, -SHADOW_RADIUS, SHADOW_RADIUS, 0.0, SHADOW_DEPTH * 2.0);
+        let view = Mat4::look_at_rh(center + sun * SHADOW_DEPTH, center, up);
+
+        let texel = SHADOW_RADIUS * 2.0 / self.resolution as f32;
+        let light_center = (view * center.extend(1.0)).xyz();
+        let snapped = (light_center / texel).round() * texel;
+        let correction = Mat4::from_translation(Vec3::new(snapped.x - light_center.x, snapped.y - light_center.y, 0.0));
+
+        projection * correction * view
+    }
+
+    pub fn begin(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo.id());
+            gl::Viewport(0, 0, self.resolution as i32, self.resolution as i32);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+    }
+}
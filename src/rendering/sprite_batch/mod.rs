@@ -0,0 +1,304 @@
+use sdl2::pixels::Color;
+use tracing::debug;
+use crate::rendering::error::GpuError;
+use crate::rendering::gpu::{self, GpuResource, GpuResourceKind};
+use crate::rendering::mesh::{VertexAttribute, VertexLayout};
+use crate::rendering::text::{FontId, Fonts};
+use crate::rendering::texture::{Texture, TextureRegion};
+
+//Position, UV and colour for every vertex
+pub const SPRITE_VERTEX_LAYOUT: VertexLayout = VertexLayout {
+    name: "sprite",
+    attributes: &[
+        VertexAttribute { name: "pos", location: 0, components: 2 },
+        VertexAttribute { name: "uv", location: 1, components: 2 },
+        VertexAttribute { name: "tint", location: 2, components: 4 },
+    ]
+};
+
+//Where on the screen a position is measured from, and which point of the element sits there
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight
+}
+
+impl Anchor {
+    fn factors(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+pub enum Shape2D {
+    Rect,
+    Sprite(TextureRegion),
+    Text(FontId, String),
+    //The border is in texels, and is drawn unscaled while the middle stretches
+    NineSlice(TextureRegion, f32)
+}
+
+pub struct Draw2D {
+    pub shape: Shape2D,
+    pub position: (f32, f32),
+    //Ignored for text, which is always its measured size
+    pub size: (f32, f32),
+    pub anchor: Anchor,
+    pub tint: Color,
+    pub layer: i32
+}
+
+impl Draw2D {
+    pub fn rect(position: (f32, f32), size: (f32, f32), color: Color) -> Self {
+        Self::new(Shape2D::Rect, position, size, color)
+    }
+
+    pub fn sprite(texture: TextureRegion, position: (f32, f32), size: (f32, f32)) -> Self {
+        Self::new(Shape2D::Sprite(texture), position, size, Color::WHITE)
+    }
+
+    pub fn text(font: FontId, text: impl Into<String>, position: (f32, f32), color: Color) -> Self {
+        Self::new(Shape2D::Text(font, text.into()), position, (0.0, 0.0), color)
+    }
+
+    pub fn nine_slice(texture: TextureRegion, border: f32, position: (f32, f32), size: (f32, f32)) -> Self {
+        Self::new(Shape2D::NineSlice(texture, border), position, size, Color::WHITE)
+    }
+
+    fn new(shape: Shape2D, position: (f32, f32), size: (f32, f32), tint: Color) -> Self {
+        Self {
+            shape,
+            position,
+            size,
+            anchor: Anchor::TopLeft,
+            tint,
+            layer: 0
+        }
+    }
+
+    pub fn anchored(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn tinted(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn on_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+struct Quad {
+    layer: i32,
+    texture_id: u32,
+    position: (f32, f32),
+    size: (f32, f32),
+    uv_min: (f32, f32),
+    uv_max: (f32, f32),
+    tint: [f32; 4]
+}
+
+//Collects every 2D element for the frame into one vertex buffer, and draws each consecutive run of
+//quads sharing a texture with a single call
+pub struct SpriteBatch {
+    vao: GpuResource,
+    vbo: GpuResource,
+    ebo: GpuResource,
+    white: Texture
+}
+
+impl SpriteBatch {
+    pub fn new() -> Result<Self, GpuError> {
+        let vao = GpuResource::new(GpuResourceKind::VertexArray)?;
+        let vbo = GpuResource::new(GpuResourceKind::Buffer)?;
+        let ebo = GpuResource::new(GpuResourceKind::Buffer)?;
+
+        unsafe {
+            gl::BindVertexArray(vao.id());
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo.id());
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo.id());
+
+            SPRITE_VERTEX_LAYOUT.enable_attributes();
+
+            gl::BindVertexArray(0);
+        }
+
+        Ok(Self { vao, vbo, ebo, white: Texture::white()? })
+    }
+
+    //Returns the number of draw calls issued
+    pub fn draw(&mut self, fonts: &Fonts, screen_size: (f32, f32), draws: &[Draw2D]) -> usize {
+        let mut quads = Vec::new();
+        for draw in draws {
+            self.push_quads(fonts, screen_size, draw, &mut quads);
+        }
+
+        //Stable, so quads on the same layer keep the order they were submitted in and overlap the way
+        //they were asked to, whatever their textures. Neighbours sharing a texture still batch below
+        quads.sort_by_key(|quad| quad.layer);
+
+        let mut vertices = Vec::with_capacity(quads.len() * 4 * SPRITE_VERTEX_LAYOUT.floats_per_vertex());
+        let mut indices = Vec::with_capacity(quads.len() * 6);
+        let mut batches: Vec<(u32, usize, usize)> = Vec::new();
+
+        for quad in &quads {
+            let base_index = (vertices.len() / SPRITE_VERTEX_LAYOUT.floats_per_vertex()) as u32;
+            let (x, y) = quad.position;
+            let (w, h) = quad.size;
+            let corners = [
+                (x, y, quad.uv_min.0, quad.uv_min.1),
+                (x + w, y, quad.uv_max.0, quad.uv_min.1),
+                (x + w, y + h, quad.uv_max.0, quad.uv_max.1),
+                (x, y + h, quad.uv_min.0, quad.uv_max.1),
+            ];
+
+            for (px, py, u, v) in corners {
+                vertices.extend_from_slice(&[px, py, u, v]);
+                vertices.extend_from_slice(&quad.tint);
+            }
+
+            match batches.last_mut() {
+                Some((texture_id, _, count)) if *texture_id == quad.texture_id => *count += 6,
+                _ => batches.push((quad.texture_id, indices.len(), 6))
+            }
+
+            indices.extend_from_slice(&[
+                base_index, base_index + 1, base_index + 2,
+                base_index + 2, base_index + 3, base_index
+            ]);
+        }
+
+        if indices.is_empty() {
+            return 0;
+        }
+
+        debug!("Drawing {} 2D quads in {} batches.", quads.len(), batches.len());
+
+        unsafe {
+            gl::BindVertexArray(self.vao.id());
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo.id());
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(vertices.as_slice()) as isize,
+                vertices.as_ptr().cast(),
+                gl::STREAM_DRAW
+            );
+
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo.id());
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                std::mem::size_of_val(indices.as_slice()) as isize,
+                indices.as_ptr().cast(),
+                gl::STREAM_DRAW
+            );
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            for &(texture_id, first_index, count) in &batches {
+                gl::BindTexture(gl::TEXTURE_2D, texture_id);
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    count as i32,
+                    gl::UNSIGNED_INT,
+                    (first_index * std::mem::size_of::<u32>()) as *const _
+                );
+            }
+        }
+
+        gpu::count_draw_calls(batches.len());
+        batches.len()
+    }
+
+    fn push_quads(&self, fonts: &Fonts, screen_size: (f32, f32), draw: &Draw2D, quads: &mut Vec<Quad>) {
+        let size = match &draw.shape {
+            Shape2D::Text(font, text) => fonts.measure(*font, text),
+            _ => draw.size
+        };
+
+        //Anchor both the element to the screen and the screen to the element, so Bottom sits
+        //centred along the bottom edge and BottomRight tucks into the corner
+        let (fx, fy) = draw.anchor.factors();
+        let origin = (
+            screen_size.0 * fx + draw.position.0 - size.0 * fx,
+            screen_size.1 * fy + draw.position.1 - size.1 * fy
+        );
+
+        let tint = [
+            draw.tint.r as f32 / 255.0,
+            draw.tint.g as f32 / 255.0,
+            draw.tint.b as f32 / 255.0,
+            draw.tint.a as f32 / 255.0
+        ];
+        let quad = |texture_id: u32, position: (f32, f32), size: (f32, f32), uv_min: (f32, f32), uv_max: (f32, f32)| Quad {
+            layer: draw.layer,
+            texture_id,
+            position,
+            size,
+            uv_min,
+            uv_max,
+            tint
+        };
+
+        match &draw.shape {
+            Shape2D::Rect => {
+                let white = self.white.region();
+                quads.push(quad(white.texture_id, origin, size, white.uv_min, white.uv_max));
+            }
+            Shape2D::Sprite(region) => {
+                quads.push(quad(region.texture_id, origin, size, region.uv_min, region.uv_max));
+            }
+            Shape2D::Text(font, text) => {
+                let atlas = fonts.get(*font);
+                for glyph in atlas.layout(text) {
+                    let position = (origin.0 + glyph.position.0, origin.1 + glyph.position.1);
+                    quads.push(quad(atlas.texture_id(), position, glyph.size, glyph.uv_min, glyph.uv_max));
+                }
+            }
+            Shape2D::NineSlice(region, border) => {
+                //Split both the screen rectangle and the texture region into three columns and rows
+                let border = border.min(size.0 / 2.0).min(size.1 / 2.0);
+                let uv_border = (
+                    border / region.size.0 * (region.uv_max.0 - region.uv_min.0),
+                    border / region.size.1 * (region.uv_max.1 - region.uv_min.1)
+                );
+
+                let xs = [origin.0, origin.0 + border, origin.0 + size.0 - border, origin.0 + size.0];
+                let ys = [origin.1, origin.1 + border, origin.1 + size.1 - border, origin.1 + size.1];
+                let us = [region.uv_min.0, region.uv_min.0 + uv_border.0, region.uv_max.0 - uv_border.0, region.uv_max.0];
+                let vs = [region.uv_min.1, region.uv_min.1 + uv_border.1, region.uv_max.1 - uv_border.1, region.uv_max.1];
+
+                for row in 0..3 {
+                    for column in 0..3 {
+                        let position = (xs[column], ys[row]);
+                        let size = (xs[column + 1] - xs[column], ys[row + 1] - ys[row]);
+                        if size.0 <= 0.0 || size.1 <= 0.0 {
+                            continue;
+                        }
+
+                        quads.push(quad(region.texture_id, position, size, (us[column], vs[row]), (us[column + 1], vs[row + 1])));
+                    }
+                }
+            }
+        }
+    }
+}
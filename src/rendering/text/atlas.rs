@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::ttf::Font;
+use tracing::debug;
+use crate::rendering::error::FontError;
+use crate::rendering::texture::Texture;
+
+const ATLAS_WIDTH: u32 = 512;
+const GLYPH_PADDING: u32 = 1;
+const FIRST_CHAR: u8 = b' ';
+const LAST_CHAR: u8 = b'~';
+const FALLBACK_CHAR: char = '?';
+
+pub struct Glyph {
+    pub uv_min: (f32, f32),
+    pub uv_max: (f32, f32),
+    pub size: (f32, f32),
+    pub advance: f32
+}
+
+//A glyph quad in pixel space, relative to the top-left of the laid out text
+pub struct GlyphQuad {
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    pub uv_min: (f32, f32),
+    pub uv_max: (f32, f32)
+}
+
+struct GlyphBitmap {
+    character: char,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    advance: f32
+}
+
+//Every printable ASCII glyph rasterised once, white, into a single texture - colour is applied when drawing
+pub struct FontAtlas {
+    texture: Texture,
+    glyphs: HashMap<char, Glyph>,
+    line_height: f32
+}
+
+impl FontAtlas {
+    pub fn from_font(font: &Font) -> Result<FontAtlas, FontError> {
+        let mut bitmaps = Vec::new();
+        for code in FIRST_CHAR..=LAST_CHAR {
+            let character = code as char;
+            let Some(metrics) = font.find_glyph_metrics(character) else {
+                debug!("Font has no glyph for {:?}, skipping.", character);
+                continue;
+            };
+
+            bitmaps.push(rasterise_glyph(font, character, metrics.advance as f32)?);
+        }
+
+        //Pack glyphs into rows left to right, starting a new row when we run out of width
+        let mut placements = Vec::with_capacity(bitmaps.len());
+        let (mut x, mut y, mut row_height) = (GLYPH_PADDING, GLYPH_PADDING, 0);
+        for bitmap in &bitmaps {
+            if x + bitmap.width + GLYPH_PADDING > ATLAS_WIDTH {
+                x = GLYPH_PADDING;
+                y += row_height + GLYPH_PADDING;
+                row_height = 0;
+            }
+
+            placements.push((x, y));
+            x += bitmap.width + GLYPH_PADDING;
+            row_height = row_height.max(bitmap.height);
+        }
+        let atlas_height = (y + row_height + GLYPH_PADDING).next_power_of_two();
+
+        let mut atlas_pixels = vec![0u8; (ATLAS_WIDTH * atlas_height * 4) as usize];
+        let mut glyphs = HashMap::new();
+        for (bitmap, &(x, y)) in bitmaps.iter().zip(placements.iter()) {
+            let row_bytes = (bitmap.width * 4) as usize;
+            for row in 0..bitmap.height {
+                let src = (row * bitmap.width * 4) as usize;
+                let dst = (((y + row) * ATLAS_WIDTH + x) * 4) as usize;
+                atlas_pixels[dst..dst + row_bytes].copy_from_slice(&bitmap.pixels[src..src + row_bytes]);
+            }
+
+            glyphs.insert(bitmap.character, Glyph {
+                uv_min: (x as f32 / ATLAS_WIDTH as f32, y as f32 / atlas_height as f32),
+                uv_max: ((x + bitmap.width) as f32 / ATLAS_WIDTH as f32, (y + bitmap.height) as f32 / atlas_height as f32),
+                size: (bitmap.width as f32, bitmap.height as f32),
+                advance: bitmap.advance
+            });
+        }
+
+        debug!("Packed {} glyphs into a {}x{} atlas.", glyphs.len(), ATLAS_WIDTH, atlas_height);
+
+        let texture = Texture::from_rgba(ATLAS_WIDTH, atlas_height, &atlas_pixels)?;
+
+        Ok(FontAtlas {
+            texture,
+            glyphs,
+            line_height: font.recommended_line_spacing() as f32
+        })
+    }
+
+    pub fn texture_id(&self) -> u32 {
+        self.texture.id()
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character).or_else(|| self.glyphs.get(&FALLBACK_CHAR))
+    }
+
+    pub fn layout(&self, text: &str) -> Vec<GlyphQuad> {
+        let mut quads = Vec::with_capacity(text.len());
+        let (mut x, mut y) = (0.0, 0.0);
+
+        for character in text.chars() {
+            if character == '\n' {
+                x = 0.0;
+                y += self.line_height;
+                continue;
+            }
+
+            let Some(glyph) = self.glyph(character) else {
+                continue;
+            };
+
+            if glyph.size.0 > 0.0 && glyph.size.1 > 0.0 {
+                quads.push(GlyphQuad {
+                    position: (x, y),
+                    size: glyph.size,
+                    uv_min: glyph.uv_min,
+                    uv_max: glyph.uv_max
+                });
+            }
+            x += glyph.advance;
+        }
+
+        quads
+    }
+
+    pub fn measure(&self, text: &str) -> (f32, f32) {
+        let mut width: f32 = 0.0;
+        let mut line_count = 0;
+
+        for line in text.split('\n') {
+            let line_width: f32 = line.chars().filter_map(|c| self.glyph(c)).map(|g| g.advance).sum();
+            width = width.max(line_width);
+            line_count += 1;
+        }
+
+        (width, line_count as f32 * self.line_height)
+    }
+}
+
+fn rasterise_glyph(font: &Font, character: char, advance: f32) -> Result<GlyphBitmap, FontError> {
+    //Whitespace has nothing to draw, SDL_ttf refuses to render it on some versions
+    if character.is_whitespace() {
+        return Ok(GlyphBitmap { character, width: 0, height: 0, pixels: Vec::new(), advance });
+    }
+
+    let surface = font
+        .render_char(character)
+        .blended(Color::WHITE)
+        .map_err(|error| FontError::Rasterise { character, message: error.to_string() })?
+        .convert_format(PixelFormatEnum::RGBA32)
+        .map_err(|message| FontError::Rasterise { character, message })?;
+
+    let (width, height, pitch) = (surface.width(), surface.height(), surface.pitch() as usize);
+    let raw = surface.without_lock().ok_or(FontError::GlyphPixels(character))?;
+
+    //Surface rows can be padded, so copy them out tightly packed
+    let row_bytes = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in 0..height as usize {
+        pixels.extend_from_slice(&raw[row * pitch..row * pitch + row_bytes]);
+    }
+
+    Ok(GlyphBitmap { character, width, height, pixels, advance })
+}
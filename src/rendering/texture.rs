@@ -0,0 +1,237 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use tracing::{debug, warn};
+use crate::assets::Assets;
+use crate::error::report;
+use crate::rendering::error::{GpuError, TextureError};
+use crate::rendering::gpu::{GpuResource, GpuResourceKind};
+use crate::world::block::BlockRegistry;
+
+pub struct Texture {
+    texture: GpuResource,
+    width: u32,
+    height: u32
+}
+
+#[derive(Clone, Copy)]
+pub struct TextureRegion {
+    pub texture_id: u32,
+    pub uv_min: (f32, f32),
+    pub uv_max: (f32, f32),
+    //Size of the region in texels, used by nine-slices to work out their borders
+    pub size: (f32, f32)
+}
+
+impl Texture {
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Result<Texture, GpuError> {
+        debug!("Uploading {}x{} RGBA texture...", width, height);
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        let texture = GpuResource::new(GpuResourceKind::Texture)?;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture.id());
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr().cast(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        }
+
+        Ok(Texture { texture, width, height })
+    }
+
+    pub fn white() -> Result<Texture, GpuError> {
+        Texture::from_rgba(1, 1, &[255, 255, 255, 255])
+    }
+
+    pub fn id(&self) -> u32 {
+        self.texture.id()
+    }
+
+    pub fn region(&self) -> TextureRegion {
+        TextureRegion {
+            texture_id: self.id(),
+            uv_min: (0.0, 0.0),
+            uv_max: (1.0, 1.0),
+            size: (self.width as f32, self.height as f32)
+        }
+    }
+}
+
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>
+}
+
+impl Image {
+    pub fn load_png(assets: &Assets, name: &str) -> Result<Image, TextureError> {
+        debug!("Loading PNG {}...", name);
+        let bytes = assets.read(name)?;
+        Self::decode_png(name, &bytes)
+    }
+
+    //The name is only used to say which image failed to decode
+    pub fn decode_png(name: &str, bytes: &[u8]) -> Result<Image, TextureError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info().map_err(|source| TextureError::Decode { name: name.to_string(), source })?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|source| TextureError::Decode { name: name.to_string(), source })?;
+        buffer.truncate(info.buffer_size());
+
+        //Everything is uploaded as RGBA, so widen whatever the file had
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buffer.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+            png::ColorType::Indexed => return Err(TextureError::Indexed { name: name.to_string() })
+        };
+
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels
+        })
+    }
+
+    //Always written as 8 bit RGBA, creating the directory it goes in if needed
+    pub fn save_png(&self, path: &Path) -> Result<(), TextureError> {
+        debug!("Saving {}x{} PNG to {}...", self.width, self.height, path.display());
+        let encode_error = |source| TextureError::Encode { path: path.to_path_buf(), source };
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| encode_error(png::EncodingError::from(error)))?;
+        }
+        let file = File::create(path).map_err(|error| encode_error(png::EncodingError::from(error)))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(encode_error)?;
+        writer.write_image_data(&self.pixels).map_err(encode_error)?;
+        writer.finish().map_err(encode_error)
+    }
+
+    //How many pixels have a channel further than the tolerance from the other image's, for
+    //comparing renders against reference images. Images of different sizes differ everywhere
+    pub fn differing_pixels(&self, other: &Image, tolerance: u8) -> usize {
+        if self.width != other.width || self.height != other.height {
+            return (self.width * self.height).max(other.width * other.height) as usize;
+        }
+
+        self.pixels.chunks(4)
+            .zip(other.pixels.chunks(4))
+            .filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance))
+            .count()
+    }
+
+    //Magenta and black checkerboard, so missing textures are obvious in game
+    pub fn missing(size: u32) -> Image {
+        let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                if (x < size / 2) == (y < size / 2) {
+                    pixels.extend_from_slice(&[255, 0, 255, 255]);
+                } else {
+                    pixels.extend_from_slice(&[0, 0, 0, 255]);
+                }
+            }
+        }
+
+        Image { width: size, height: size, pixels }
+    }
+}
+
+//A stack of equally sized layers, sampled with a layer index so blocks never bleed into each other
+pub struct TextureArray {
+    texture: GpuResource,
+    layers: u32
+}
+
+impl TextureArray {
+    pub fn from_images(size: u32, images: &[Image]) -> Result<TextureArray, TextureError> {
+        if let Some(image) = images.iter().find(|image| image.width != size || image.height != size) {
+            return Err(TextureError::LayerSize { size, width: image.width, height: image.height });
+        }
+
+        debug!("Uploading {} layer texture array of {}x{}...", images.len(), size, size);
+        let mut pixels = Vec::with_capacity((size * size * 4) as usize * images.len());
+        for image in images {
+            pixels.extend_from_slice(&image.pixels);
+        }
+
+        let texture = GpuResource::new(GpuResourceKind::Texture)?;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, texture.id());
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                gl::RGBA as i32,
+                size as i32,
+                size as i32,
+                images.len() as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr().cast(),
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D_ARRAY);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, gl::NEAREST_MIPMAP_LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+        }
+
+        Ok(TextureArray { texture, layers: images.len() as u32 })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.texture.id()
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+}
+
+pub const BLOCK_TEXTURE_DIRECTORY: &str = "textures/blocks";
+pub const BLOCK_TEXTURE_SIZE: u32 = 16;
+
+//Loads every texture the registry needs, in layer order, substituting the missing texture for
+//anything that can't be read so one bad file doesn't stop the game
+pub fn load_block_textures(registry: &BlockRegistry, assets: &Assets) -> Result<TextureArray, TextureError> {
+    let images: Vec<Image> = registry
+        .texture_names()
+        .iter()
+        .map(|name| {
+            let asset = format!("{}/{}.png", BLOCK_TEXTURE_DIRECTORY, name);
+            match Image::load_png(assets, &asset) {
+                Ok(image) if image.width == BLOCK_TEXTURE_SIZE && image.height == BLOCK_TEXTURE_SIZE => image,
+                Ok(image) => {
+                    warn!("Block texture {} is {}x{}, expected {}x{}.", asset, image.width, image.height, BLOCK_TEXTURE_SIZE, BLOCK_TEXTURE_SIZE);
+                    Image::missing(BLOCK_TEXTURE_SIZE)
+                }
+                Err(error) => {
+                    warn!("{}", report(&error));
+                    Image::missing(BLOCK_TEXTURE_SIZE)
+                }
+            }
+        })
+        .collect();
+
+    TextureArray::from_images(BLOCK_TEXTURE_SIZE, &images)
+}
@@ -0,0 +1,24 @@
+//Shadow map sizes the settings menu steps through
+pub const SHADOW_RESOLUTIONS: [u32; 4] = [512, 1024, 2048, 4096];
+
+pub struct Settings {
+    pub look_sensitivity: f32,
+    pub movement_speed: f32,
+    pub shadow_resolution: u32
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            look_sensitivity: 0.01,
+            movement_speed: 0.1,
+            shadow_resolution: 2048
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
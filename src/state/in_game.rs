@@ -0,0 +1,259 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use sdl2::pixels::Color;
+use crate::debug::chunk::ChunkStates;
+use crate::debug::console::{CommandContext, Console};
+use crate::debug::panels::PanelContext;
+use crate::debug::profiler;
+use crate::events::EventQueue;
+use crate::input::InputAction;
+use crate::input::controllers::{Controller, camera_controller::CameraController, composite_controller::CompositeController, console_controller::ConsoleController, debug_overlay_controller::DebugOverlayController, interaction_controller::InteractionController};
+use crate::logging::LogBuffer;
+use crate::rendering::camera::Camera;
+use crate::rendering::lighting::Lighting;
+use crate::rendering::render_context::RenderContext;
+use crate::rendering::Renderer;
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::settings::Settings;
+use crate::world::{block, World, REACH};
+use crate::world::time::WorldClock;
+use crate::world::block::{BlockId, BlockRegistry};
+use crate::world::chunk_mesh_manager::ChunkMeshManager;
+use crate::world::save::SaveGame;
+use super::paused::Paused;
+use super::{AppContext, State, Transition};
+
+const CROSSHAIR_LENGTH: f32 = 12.0;
+const CROSSHAIR_THICKNESS: f32 = 2.0;
+const HOTBAR_SLOTS: usize = 9;
+const HOTBAR_SLOT_SIZE: f32 = 40.0;
+const HOTBAR_MARGIN: f32 = 8.0;
+
+pub struct GameSession {
+    pub camera: Camera,
+    pub world: World,
+    pub chunk_mesh_manager: Rc<RefCell<ChunkMeshManager>>,
+    pub event_queue: EventQueue,
+    pub chunk_states: ChunkStates
+}
+
+impl GameSession {
+    pub fn new(seed: u32, blocks: Rc<BlockRegistry>) -> Self {
+        Self::with_camera(seed, Camera::new(), blocks)
+    }
+
+    pub fn from_save(save: &SaveGame, blocks: Rc<BlockRegistry>) -> Self {
+        let mut world = World::with_seed(save.seed);
+        world.clock = WorldClock::at(save.time_of_day);
+        Self::with_world(world, Camera::with_pose(save.position, save.yaw, save.pitch), blocks)
+    }
+
+    fn with_camera(seed: u32, camera: Camera, blocks: Rc<BlockRegistry>) -> Self {
+        Self::with_world(World::with_seed(seed), camera, blocks)
+    }
+
+    fn with_world(world: World, camera: Camera, blocks: Rc<BlockRegistry>) -> Self {
+        let chunk_mesh_manager = Rc::new(RefCell::new(ChunkMeshManager::new(blocks)));
+        let mut event_queue = EventQueue::new();
+        event_queue.register_handler(chunk_mesh_manager.clone());
+
+        Self {
+            camera,
+            world,
+            chunk_mesh_manager,
+            event_queue,
+            chunk_states: ChunkStates::new()
+        }
+    }
+
+    pub fn update(&mut self, elapsed: Duration) {
+        self.world.clock.advance(elapsed);
+        {
+            let _scope = profiler::scope("world_update");
+            self.world.update(self.camera.position(), &mut self.event_queue);
+        }
+        self.chunk_states.observe(&self.world, &self.chunk_mesh_manager.borrow(), &self.event_queue, self.camera.position());
+        let _scope = profiler::scope("event_dispatch");
+        self.event_queue.dispatch_events();
+    }
+
+    //Returns false if nothing was in reach
+    pub fn break_block(&mut self) -> bool {
+        let Some(hit) = self.world.raycast(self.camera.position(), self.camera.forward(), REACH) else {
+            return false;
+        };
+        self.world.set_block(hit.pos, block::AIR, &mut self.event_queue)
+    }
+
+    //Placed against whichever face of the block under the crosshair the player is looking at
+    pub fn place_block(&mut self, block: BlockId) -> bool {
+        let Some(hit) = self.world.raycast(self.camera.position(), self.camera.forward(), REACH) else {
+            return false;
+        };
+        //Inside a block already, so there's no face to place against
+        if hit.previous == hit.pos {
+            return false;
+        }
+        self.world.set_block(hit.previous, block, &mut self.event_queue)
+    }
+
+    pub fn snapshot(&self) -> SaveGame {
+        SaveGame {
+            seed: self.world.seed,
+            position: self.camera.position(),
+            yaw: self.camera.yaw(),
+            pitch: self.camera.pitch(),
+            time_of_day: self.world.clock.time_of_day()
+        }
+    }
+}
+
+pub struct InGame {
+    session: GameSession,
+    console: Console,
+    hotbar_slot: usize
+}
+
+impl InGame {
+    pub fn new(session: GameSession, log: LogBuffer) -> Self {
+        Self {
+            session,
+            console: Console::new(log),
+            hotbar_slot: 0
+        }
+    }
+
+    fn hotbar(&self, renderer: &Renderer) -> Vec<Draw2D> {
+        let panel = renderer.panel_texture();
+        let total_width = HOTBAR_SLOTS as f32 * HOTBAR_SLOT_SIZE;
+
+        (0..HOTBAR_SLOTS)
+            .map(|slot| {
+                //Offsets are from the bottom centre of the screen
+                let x = slot as f32 * HOTBAR_SLOT_SIZE - (total_width - HOTBAR_SLOT_SIZE) / 2.0;
+                let draw = Draw2D::nine_slice(panel, 2.0, (x, -HOTBAR_MARGIN), (HOTBAR_SLOT_SIZE, HOTBAR_SLOT_SIZE))
+                    .anchored(Anchor::Bottom);
+
+                if slot == self.hotbar_slot {
+                    draw.tinted(Color::YELLOW).on_layer(1)
+                } else {
+                    draw
+                }
+            })
+            .collect()
+    }
+
+    //Slots past the last block are empty
+    fn selected_block(&self) -> Option<BlockId> {
+        block::placeable().nth(self.hotbar_slot)
+    }
+}
+
+impl State for InGame {
+    fn name(&self) -> &'static str {
+        "InGame"
+    }
+
+    fn controller(&self, settings: &Settings) -> Box<dyn Controller> {
+        if self.console.is_open() {
+            return Box::new(ConsoleController {});
+        }
+
+        let camera_controller = Box::new(CameraController::new(settings));
+        let interaction_controller = Box::new(InteractionController {});
+        let debug_controller = Box::new(DebugOverlayController{});
+
+        let mut controller = CompositeController::new();
+        controller.push_controller(camera_controller);
+        controller.push_controller(interaction_controller);
+        controller.push_controller(debug_controller);
+        Box::new(controller)
+    }
+
+    fn captures_mouse(&self) -> bool {
+        !self.console.is_open()
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        for action in actions {
+            match action {
+                InputAction::Quit => return Transition::Quit,
+                InputAction::Pause => return Transition::Push(Box::new(Paused::new(self.session.snapshot()))),
+                InputAction::MoveCamera(delta) => self.session.camera.move_by(delta),
+                InputAction::LookDelta(relative_direction) => self.session.camera.apply_look(relative_direction),
+                InputAction::BreakBlock => {
+                    self.session.break_block();
+                }
+                InputAction::PlaceBlock => {
+                    if let Some(block) = self.selected_block() {
+                        self.session.place_block(block);
+                    }
+                }
+                InputAction::HotbarScroll(step) => {
+                    self.hotbar_slot = (self.hotbar_slot as i32 + step).rem_euclid(HOTBAR_SLOTS as i32) as usize;
+                }
+                InputAction::ToggleDebugModule(key) => ctx.debugger.toggle_panel(key),
+                InputAction::CycleRenderMode => ctx.debugger.cycle_render_mode(),
+                InputAction::ExportTrace => ctx.debugger.export_trace(),
+                InputAction::ToggleLogView => ctx.debugger.toggle_log_view(),
+                InputAction::ToggleConsole => {
+                    self.console.toggle();
+                    return Transition::Refresh;
+                }
+                InputAction::ConsoleChar(character) => self.console.push_char(character),
+                InputAction::ConsoleErase => self.console.erase(),
+                InputAction::ConsoleComplete => self.console.complete(),
+                InputAction::ConsoleHistory(step) => self.console.browse_history(step),
+                InputAction::ConsoleScroll(lines) => self.console.scroll(lines),
+                InputAction::ConsoleSubmit => self.console.submit(&mut CommandContext {
+                    session: &mut self.session,
+                    debugger: &mut ctx.debugger
+                }),
+                _ => {}
+            }
+        }
+
+        self.session.update(ctx.frame_time);
+        Transition::None
+    }
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        let debugger = &ctx.debugger;
+        let mut overlay = vec![
+            //Crosshair
+            Draw2D::rect((0.0, 0.0), (CROSSHAIR_LENGTH, CROSSHAIR_THICKNESS), Color::WHITE).anchored(Anchor::Center),
+            Draw2D::rect((0.0, 0.0), (CROSSHAIR_THICKNESS, CROSSHAIR_LENGTH), Color::WHITE).anchored(Anchor::Center),
+        ];
+        overlay.extend(self.hotbar(renderer));
+        if let Some(definition) = self.selected_block().and_then(block::definition) {
+            let position = (0.0, -(HOTBAR_MARGIN * 2.0 + HOTBAR_SLOT_SIZE));
+            overlay.push(Draw2D::text(ctx.font, definition.name, position, Color::WHITE).anchored(Anchor::Bottom));
+        }
+
+        //Shaders that failed to hot reload, listed at the top until they compile again
+        let line_height = renderer.fonts().line_height(ctx.font);
+        let mut lines = 0;
+        for error in renderer.shader_errors().flat_map(|error| error.lines()) {
+            let position = (0.0, line_height * lines as f32);
+            overlay.push(Draw2D::text(ctx.font, error, position, Color::RED).on_layer(1));
+            lines += 1;
+        }
+        let panel_context = PanelContext { debugger, session: &self.session };
+        overlay.extend(debugger.panel_draws(&panel_context, ctx.font, line_height * lines as f32, line_height));
+        overlay.extend(debugger.log_view_draws(ctx.font, line_height));
+        overlay.extend(self.console.draws(ctx.font, line_height, renderer.screen_size().0));
+
+        let mesh_ref = self.session.chunk_mesh_manager.borrow();
+        let render_context = RenderContext {
+            camera: &self.session.camera,
+            lighting: Lighting::from_clock(&self.session.world.clock, self.session.camera.up()),
+            shadow_resolution: ctx.settings.shadow_resolution,
+            debug_mode: ctx.debugger.render_mode,
+            meshes: mesh_ref.meshes(),
+            translucent_meshes: mesh_ref.translucent_meshes(),
+            overlay
+        };
+        renderer.render(render_context);
+    }
+}
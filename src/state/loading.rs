@@ -0,0 +1,81 @@
+use std::time::Duration;
+use sdl2::pixels::Color;
+use tracing::debug;
+use crate::input::InputAction;
+use crate::input::controllers::{Controller, menu_controller::MenuController};
+use crate::rendering::Renderer;
+use crate::rendering::sprite_batch::{Anchor, Draw2D};
+use crate::settings::Settings;
+use crate::world::save::SaveGame;
+use super::in_game::{GameSession, InGame};
+use super::{AppContext, State, Transition};
+
+enum WorldSource {
+    New(u32),
+    Save(SaveGame)
+}
+
+pub struct Loading {
+    source: WorldSource,
+    frames_shown: u32
+}
+
+impl Loading {
+    pub fn new_world(seed: u32) -> Self {
+        Self {
+            source: WorldSource::New(seed),
+            frames_shown: 0
+        }
+    }
+
+    pub fn from_save(save: SaveGame) -> Self {
+        Self {
+            source: WorldSource::Save(save),
+            frames_shown: 0
+        }
+    }
+
+    fn seed(&self) -> u32 {
+        match &self.source {
+            WorldSource::New(seed) => *seed,
+            WorldSource::Save(save) => save.seed
+        }
+    }
+}
+
+impl State for Loading {
+    fn name(&self) -> &'static str {
+        "Loading"
+    }
+
+    fn controller(&self, _settings: &Settings) -> Box<dyn Controller> {
+        Box::new(MenuController {})
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        if actions.iter().any(|action| matches!(action, InputAction::Quit)) {
+            return Transition::Quit;
+        }
+
+        //Let the loading screen get drawn once before we block on generating chunks
+        self.frames_shown += 1;
+        if self.frames_shown < 2 {
+            return Transition::None;
+        }
+
+        debug!("Loading world with seed {}...", self.seed());
+        let mut session = match &self.source {
+            WorldSource::New(seed) => GameSession::new(*seed, ctx.blocks.clone()),
+            WorldSource::Save(save) => GameSession::from_save(save, ctx.blocks.clone())
+        };
+        session.update(Duration::ZERO);
+        debug!("World loaded.");
+
+        Transition::Switch(Box::new(InGame::new(session, ctx.log.clone())))
+    }
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        let message = format!("Generating world (seed: {})...", self.seed());
+        renderer.render_2d(&[Draw2D::text(ctx.title_font, message, (0.0, 0.0), Color::WHITE).anchored(Anchor::Center)]);
+    }
+}
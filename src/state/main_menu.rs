@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+use crate::error::report;
+use crate::input::InputAction;
+use crate::input::controllers::{Controller, menu_controller::MenuController};
+use crate::rendering::Renderer;
+use crate::settings::Settings;
+use crate::world::save::{self, SaveGame};
+use super::loading::Loading;
+use super::menu::Menu;
+use super::settings_menu::SettingsMenu;
+use super::{AppContext, State, Transition};
+
+const NEW_WORLD: usize = 0;
+const LOAD_WORLD: usize = 1;
+const SETTINGS: usize = 2;
+const QUIT: usize = 3;
+
+const MAX_SEED_DIGITS: usize = 10;
+
+enum Screen {
+    Main,
+    Load
+}
+
+pub struct MainMenu {
+    menu: Menu,
+    load_menu: Menu,
+    screen: Screen,
+    saves: Vec<PathBuf>,
+    seed_input: String
+}
+
+impl MainMenu {
+    pub fn new() -> Self {
+        let mut main_menu = Self {
+            menu: Menu::new("Kardashev", Vec::new()),
+            load_menu: Menu::new("Load world", Vec::new()),
+            screen: Screen::Main,
+            saves: Vec::new(),
+            seed_input: String::new()
+        };
+        main_menu.refresh_items();
+        main_menu
+    }
+
+    fn refresh_items(&mut self) {
+        let seed = if self.seed_input.is_empty() { "random" } else { &self.seed_input };
+        self.menu.items = vec![
+            format!("New world (seed: {})", seed),
+            "Load world".to_string(),
+            "Settings".to_string(),
+            "Quit".to_string()
+        ];
+    }
+
+    fn seed(&self, ctx: &AppContext) -> Result<u32, String> {
+        if self.seed_input.is_empty() {
+            if let Some(seed) = ctx.random_seed {
+                return Ok(seed);
+            }
+
+            //No seed given, so take one from the clock
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.subsec_nanos())
+                .unwrap_or(0);
+            return Ok(nanos);
+        }
+
+        self.seed_input.parse::<u32>().map_err(|_| format!("Seed must be between 0 and {}.", u32::MAX))
+    }
+
+    fn open_load_screen(&mut self) {
+        self.saves = save::list_saves();
+        debug!("Found {} saves.", self.saves.len());
+
+        let mut items: Vec<String> = self.saves
+            .iter()
+            .map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default())
+            .collect();
+        items.push("Back".to_string());
+
+        self.load_menu = Menu::new("Load world", items);
+        if self.saves.is_empty() {
+            self.load_menu.message = Some(format!("No saves found in {}/", save::SAVE_DIRECTORY));
+        }
+        self.screen = Screen::Load;
+    }
+
+    fn update_main(&mut self, action: InputAction, ctx: &AppContext) -> Transition {
+        match action {
+            InputAction::MenuNavigate(delta) => self.menu.navigate(delta),
+            //Seeds are numeric, so anything else typed is ignored
+            InputAction::MenuChar(c) if c.is_ascii_digit() && self.menu.selected() == NEW_WORLD && self.seed_input.len() < MAX_SEED_DIGITS => {
+                self.seed_input.push(c);
+                self.refresh_items();
+            }
+            InputAction::MenuErase if self.menu.selected() == NEW_WORLD => {
+                self.seed_input.pop();
+                self.refresh_items();
+            }
+            InputAction::MenuSelect => match self.menu.selected() {
+                NEW_WORLD => match self.seed(ctx) {
+                    Ok(seed) => return Transition::Switch(Box::new(Loading::new_world(seed))),
+                    Err(message) => self.menu.message = Some(message)
+                },
+                LOAD_WORLD => self.open_load_screen(),
+                SETTINGS => return Transition::Push(Box::new(SettingsMenu::new(&ctx.settings))),
+                QUIT => return Transition::Quit,
+                _ => {}
+            },
+            InputAction::Quit => return Transition::Quit,
+            _ => {}
+        }
+
+        Transition::None
+    }
+
+    fn update_load(&mut self, action: InputAction) -> Transition {
+        match action {
+            InputAction::MenuNavigate(delta) => self.load_menu.navigate(delta),
+            InputAction::MenuBack => self.screen = Screen::Main,
+            InputAction::MenuSelect => {
+                let Some(path) = self.saves.get(self.load_menu.selected()) else {
+                    //The last entry is always "Back"
+                    self.screen = Screen::Main;
+                    return Transition::None;
+                };
+
+                match SaveGame::read(path) {
+                    Ok(save) => return Transition::Switch(Box::new(Loading::from_save(save))),
+                    Err(error) => {
+                        warn!("{}", report(&error));
+                        self.load_menu.message = Some(error.to_string());
+                    }
+                }
+            }
+            InputAction::Quit => return Transition::Quit,
+            _ => {}
+        }
+
+        Transition::None
+    }
+}
+
+impl Default for MainMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State for MainMenu {
+    fn name(&self) -> &'static str {
+        "MainMenu"
+    }
+
+    fn controller(&self, _settings: &Settings) -> Box<dyn Controller> {
+        Box::new(MenuController {})
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        for action in actions {
+            let transition = match self.screen {
+                Screen::Main => self.update_main(action, ctx),
+                Screen::Load => self.update_load(action)
+            };
+
+            if !matches!(transition, Transition::None) {
+                return transition;
+            }
+        }
+
+        Transition::None
+    }
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        let menu = match self.screen {
+            Screen::Main => &self.menu,
+            Screen::Load => &self.load_menu
+        };
+
+        let draws = menu.draws(renderer.fonts(), ctx.font, ctx.title_font, renderer.panel_texture());
+        renderer.render_2d(&draws);
+    }
+}
@@ -0,0 +1,75 @@
+use sdl2::pixels::Color;
+use crate::rendering::sprite_batch::Draw2D;
+use crate::rendering::text::{FontId, Fonts};
+use crate::rendering::texture::TextureRegion;
+
+const MENU_ORIGIN: (f32, f32) = (40.0, 40.0);
+const PANEL_PADDING: f32 = 16.0;
+const PANEL_BORDER: f32 = 2.0;
+
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<String>,
+    pub message: Option<String>,
+    selected: usize
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            message: None,
+            selected: 0
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn navigate(&mut self, delta: i32) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let count = self.items.len() as i32;
+        self.selected = (self.selected as i32 + delta).rem_euclid(count) as usize;
+    }
+
+    pub fn draws(&self, fonts: &Fonts, font: FontId, title_font: FontId, panel: TextureRegion) -> Vec<Draw2D> {
+        let (x, mut y) = MENU_ORIGIN;
+        let line_height = fonts.line_height(font);
+        let mut draws = Vec::new();
+        let mut width = fonts.measure(title_font, &self.title).0;
+
+        draws.push(Draw2D::text(title_font, self.title.as_str(), (x, y), Color::WHITE).on_layer(1));
+        y += fonts.line_height(title_font) + line_height;
+
+        for (i, item) in self.items.iter().enumerate() {
+            let (line, color) = if i == self.selected {
+                (format!("> {}", item), Color::YELLOW)
+            } else {
+                (format!("  {}", item), Color::GRAY)
+            };
+
+            width = width.max(fonts.measure(font, &line).0);
+            draws.push(Draw2D::text(font, line, (x, y), color).on_layer(1));
+            y += line_height;
+        }
+
+        if let Some(message) = &self.message {
+            y += line_height;
+            width = width.max(fonts.measure(font, message).0);
+            draws.push(Draw2D::text(font, message.as_str(), (x, y), Color::RED).on_layer(1));
+            y += line_height;
+        }
+
+        //Backing panel sized to fit everything above
+        let panel_position = (x - PANEL_PADDING, MENU_ORIGIN.1 - PANEL_PADDING);
+        let panel_size = (width + PANEL_PADDING * 2.0, y - MENU_ORIGIN.1 + PANEL_PADDING * 2.0);
+        draws.push(Draw2D::nine_slice(panel, PANEL_BORDER, panel_position, panel_size));
+
+        draws
+    }
+}
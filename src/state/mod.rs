@@ -0,0 +1,135 @@
+pub mod in_game;
+pub mod loading;
+pub mod main_menu;
+pub mod menu;
+pub mod paused;
+pub mod settings_menu;
+
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::debug;
+use crate::debug::DebugOverlay;
+use crate::input::InputAction;
+use crate::input::controllers::Controller;
+use crate::logging::LogBuffer;
+use crate::rendering::Renderer;
+use crate::rendering::text::FontId;
+use crate::settings::Settings;
+use crate::world::block::BlockRegistry;
+
+//Everything which outlives a single state - states borrow this rather than owning it
+pub struct AppContext {
+    pub font: FontId,
+    pub title_font: FontId,
+    pub debugger: DebugOverlay,
+    pub settings: Settings,
+    pub blocks: Rc<BlockRegistry>,
+    //Recent log records, for anything that shows the log in game
+    pub log: LogBuffer,
+    //Used for new worlds instead of a seed from the clock, so a recorded session replays into the
+    //same world
+    pub random_seed: Option<u32>,
+    pub frame_time: Duration
+}
+
+pub enum Transition {
+    None,
+    Push(Box<dyn State>),
+    Pop,
+    Switch(Box<dyn State>),
+    Reset(Box<dyn State>),
+    //Stays in the current state, but picks up its controller and mouse capture again
+    Refresh,
+    Quit
+}
+
+pub trait State {
+    fn name(&self) -> &'static str;
+
+    //Called whenever this state becomes the active one, so settings changes are picked up
+    fn controller(&self, settings: &Settings) -> Box<dyn Controller>;
+
+    fn captures_mouse(&self) -> bool {
+        false
+    }
+
+    //Overlays are drawn on top of whatever state is beneath them, rather than replacing it
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition;
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext);
+}
+
+pub struct StateMachine {
+    states: Vec<Box<dyn State>>
+}
+
+impl StateMachine {
+    pub fn new(initial: Box<dyn State>) -> Self {
+        debug!("State machine starting in {}.", initial.name());
+        Self {
+            states: vec![initial]
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.states.is_empty()
+    }
+
+    pub fn current(&self) -> Option<&dyn State> {
+        self.states.last().map(|state| state.as_ref())
+    }
+
+    pub fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        match self.states.last_mut() {
+            Some(state) => state.update(actions, ctx),
+            None => Transition::Quit
+        }
+    }
+
+    //Returns true if the active state changed
+    pub fn apply(&mut self, transition: Transition) -> bool {
+        match transition {
+            Transition::None => return false,
+            Transition::Push(state) => {
+                debug!("Pushing state {}.", state.name());
+                self.states.push(state);
+            }
+            Transition::Pop => {
+                if let Some(state) = self.states.pop() {
+                    debug!("Popping state {}.", state.name());
+                }
+            }
+            Transition::Switch(state) => {
+                debug!("Switching to state {}.", state.name());
+                self.states.pop();
+                self.states.push(state);
+            }
+            Transition::Reset(state) => {
+                debug!("Resetting state stack to {}.", state.name());
+                self.states.clear();
+                self.states.push(state);
+            }
+            Transition::Refresh => {
+                debug!("Refreshing state {}.", self.states.last().map_or("none", |state| state.name()));
+            }
+            Transition::Quit => {
+                debug!("Quitting - clearing state stack.");
+                self.states.clear();
+            }
+        }
+
+        true
+    }
+
+    pub fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        //Draw from the top-most opaque state upwards, so overlays sit on what they cover
+        let base = self.states.iter().rposition(|state| !state.is_overlay()).unwrap_or(0);
+        for state in &self.states[base..] {
+            state.render(renderer, ctx);
+        }
+    }
+}
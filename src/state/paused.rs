@@ -0,0 +1,87 @@
+use tracing::warn;
+use crate::error::report;
+use crate::input::InputAction;
+use crate::input::controllers::{Controller, menu_controller::MenuController};
+use crate::rendering::Renderer;
+use crate::settings::Settings;
+use crate::world::save::SaveGame;
+use super::main_menu::MainMenu;
+use super::menu::Menu;
+use super::settings_menu::SettingsMenu;
+use super::{AppContext, State, Transition};
+
+const RESUME: usize = 0;
+const SAVE: usize = 1;
+const SETTINGS: usize = 2;
+const QUIT_TO_MENU: usize = 3;
+const QUIT_GAME: usize = 4;
+
+pub struct Paused {
+    menu: Menu,
+    snapshot: SaveGame
+}
+
+impl Paused {
+    pub fn new(snapshot: SaveGame) -> Self {
+        let items = vec![
+            "Resume".to_string(),
+            "Save world".to_string(),
+            "Settings".to_string(),
+            "Quit to main menu".to_string(),
+            "Quit game".to_string()
+        ];
+
+        Self {
+            menu: Menu::new("Paused", items),
+            snapshot
+        }
+    }
+}
+
+impl State for Paused {
+    fn name(&self) -> &'static str {
+        "Paused"
+    }
+
+    fn controller(&self, _settings: &Settings) -> Box<dyn Controller> {
+        Box::new(MenuController {})
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        for action in actions {
+            match action {
+                InputAction::MenuNavigate(delta) => self.menu.navigate(delta),
+                InputAction::MenuBack => return Transition::Pop,
+                InputAction::MenuSelect => match self.menu.selected() {
+                    RESUME => return Transition::Pop,
+                    SAVE => {
+                        self.menu.message = Some(match self.snapshot.write() {
+                            Ok(path) => format!("Saved to {}", path.display()),
+                            Err(error) => {
+                                warn!("{}", report(&error));
+                                error.to_string()
+                            }
+                        });
+                    }
+                    SETTINGS => return Transition::Push(Box::new(SettingsMenu::new(&ctx.settings))),
+                    QUIT_TO_MENU => return Transition::Reset(Box::new(MainMenu::new())),
+                    QUIT_GAME => return Transition::Quit,
+                    _ => {}
+                },
+                InputAction::Quit => return Transition::Quit,
+                _ => {}
+            }
+        }
+
+        Transition::None
+    }
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        let draws = self.menu.draws(renderer.fonts(), ctx.font, ctx.title_font, renderer.panel_texture());
+        renderer.render_2d(&draws);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::input::InputAction;
+use crate::input::controllers::{Controller, menu_controller::MenuController};
+use crate::rendering::Renderer;
+use crate::settings::{Settings, SHADOW_RESOLUTIONS};
+use super::menu::Menu;
+use super::{AppContext, State, Transition};
+
+const LOOK_SENSITIVITY: usize = 0;
+const MOVEMENT_SPEED: usize = 1;
+const SHADOW_RESOLUTION: usize = 2;
+const BACK: usize = 3;
+
+const LOOK_SENSITIVITY_STEP: f32 = 0.001;
+const MOVEMENT_SPEED_STEP: f32 = 0.01;
+
+pub struct SettingsMenu {
+    menu: Menu
+}
+
+impl SettingsMenu {
+    pub fn new(settings: &Settings) -> Self {
+        let mut settings_menu = Self {
+            menu: Menu::new("Settings", Vec::new())
+        };
+        settings_menu.refresh_items(settings);
+        settings_menu
+    }
+
+    fn refresh_items(&mut self, settings: &Settings) {
+        self.menu.items = vec![
+            format!("Look sensitivity: < {:.3} >", settings.look_sensitivity),
+            format!("Movement speed: < {:.2} >", settings.movement_speed),
+            format!("Shadow resolution: < {} >", settings.shadow_resolution),
+            "Back".to_string()
+        ];
+    }
+
+    fn adjust(&self, settings: &mut Settings, delta: i32) {
+        match self.menu.selected() {
+            LOOK_SENSITIVITY => {
+                settings.look_sensitivity = (settings.look_sensitivity + delta as f32 * LOOK_SENSITIVITY_STEP).max(LOOK_SENSITIVITY_STEP);
+            }
+            MOVEMENT_SPEED => {
+                settings.movement_speed = (settings.movement_speed + delta as f32 * MOVEMENT_SPEED_STEP).max(MOVEMENT_SPEED_STEP);
+            }
+            SHADOW_RESOLUTION => {
+                let current = SHADOW_RESOLUTIONS.iter().position(|&r| r == settings.shadow_resolution).unwrap_or(0) as i32;
+                let next = (current + delta).clamp(0, SHADOW_RESOLUTIONS.len() as i32 - 1);
+                settings.shadow_resolution = SHADOW_RESOLUTIONS[next as usize];
+            }
+            _ => {}
+        }
+    }
+}
+
+impl State for SettingsMenu {
+    fn name(&self) -> &'static str {
+        "Settings"
+    }
+
+    fn controller(&self, _settings: &Settings) -> Box<dyn Controller> {
+        Box::new(MenuController {})
+    }
+
+    fn update(&mut self, actions: Vec<InputAction>, ctx: &mut AppContext) -> Transition {
+        for action in actions {
+            match action {
+                InputAction::MenuNavigate(delta) => self.menu.navigate(delta),
+                InputAction::MenuAdjust(delta) => self.adjust(&mut ctx.settings, delta),
+                InputAction::MenuBack => return Transition::Pop,
+                InputAction::MenuSelect if self.menu.selected() == BACK => return Transition::Pop,
+                InputAction::Quit => return Transition::Quit,
+                _ => {}
+            }
+        }
+
+        self.refresh_items(&ctx.settings);
+        Transition::None
+    }
+
+    fn render(&self, renderer: &mut Renderer, ctx: &AppContext) {
+        let draws = self.menu.draws(renderer.fonts(), ctx.font, ctx.title_font, renderer.panel_texture());
+        renderer.render_2d(&draws);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use tracing::debug;
+
+pub type BlockId = u8;
+
+pub const AIR: BlockId = 0;
+pub const GRASS: BlockId = 1;
+pub const DIRT: BlockId = 2;
+pub const STONE: BlockId = 3;
+pub const LAMP: BlockId = 4;
+pub const WATER: BlockId = 5;
+pub const GLASS: BlockId = 6;
+pub const LEAVES: BlockId = 7;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Face {
+    Top,
+    Bottom,
+    North,
+    South,
+    East,
+    West
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::Top, Face::Bottom, Face::North, Face::South, Face::East, Face::West];
+
+    pub fn normal(&self) -> (i32, i32, i32) {
+        match self {
+            Face::Top => (0, 1, 0),
+            Face::Bottom => (0, -1, 0),
+            Face::North => (0, 0, -1),
+            Face::South => (0, 0, 1),
+            Face::East => (1, 0, 0),
+            Face::West => (-1, 0, 0),
+        }
+    }
+}
+
+//Texture names are looked up as the textures/blocks/<name>.png asset
+pub struct BlockTextures {
+    pub top: &'static str,
+    pub side: &'static str,
+    pub bottom: &'static str
+}
+
+impl BlockTextures {
+    pub const fn all(name: &'static str) -> Self {
+        Self { top: name, side: name, bottom: name }
+    }
+
+    pub fn for_face(&self, face: Face) -> &'static str {
+        match face {
+            Face::Top => self.top,
+            Face::Bottom => self.bottom,
+            _ => self.side
+        }
+    }
+}
+
+pub struct BlockDefinition {
+    pub id: BlockId,
+    pub name: &'static str,
+    pub textures: BlockTextures,
+    //Opaque blocks stop light, and hide the faces of blocks next to them
+    pub opaque: bool,
+    //Translucent blocks are blended over the rest of the world in a second pass. Blocks that are
+    //neither opaque nor translucent are cut out, with see-through texels discarded
+    pub translucent: bool,
+    //Block light level given off, from 0 to MAX_LIGHT
+    pub emission: u8
+}
+
+const BLOCKS: &[BlockDefinition] = &[
+    BlockDefinition {
+        id: GRASS,
+        name: "grass",
+        textures: BlockTextures { top: "grass_top", side: "grass_side", bottom: "dirt" },
+        opaque: true,
+        translucent: false,
+        emission: 0
+    },
+    BlockDefinition {
+        id: DIRT,
+        name: "dirt",
+        textures: BlockTextures::all("dirt"),
+        opaque: true,
+        translucent: false,
+        emission: 0
+    },
+    BlockDefinition {
+        id: STONE,
+        name: "stone",
+        textures: BlockTextures::all("stone"),
+        opaque: true,
+        translucent: false,
+        emission: 0
+    },
+    BlockDefinition {
+        id: LAMP,
+        name: "lamp",
+        textures: BlockTextures::all("lamp"),
+        opaque: true,
+        translucent: false,
+        emission: 14
+    },
+    BlockDefinition {
+        id: WATER,
+        name: "water",
+        textures: BlockTextures::all("water"),
+        opaque: false,
+        translucent: true,
+        emission: 0
+    },
+    BlockDefinition {
+        id: GLASS,
+        name: "glass",
+        textures: BlockTextures::all("glass"),
+        opaque: false,
+        translucent: true,
+        emission: 0
+    },
+    BlockDefinition {
+        id: LEAVES,
+        name: "leaves",
+        textures: BlockTextures::all("leaves"),
+        opaque: false,
+        translucent: false,
+        emission: 0
+    },
+];
+
+//Air isn't defined as a block, but can still be placed by name
+pub fn by_name(name: &str) -> Option<BlockId> {
+    if name == "air" {
+        return Some(AIR);
+    }
+    BLOCKS.iter().find(|block| block.name == name).map(|block| block.id)
+}
+
+//Every defined block, in the order the hotbar offers them
+pub fn placeable() -> impl Iterator<Item = BlockId> {
+    BLOCKS.iter().map(|block| block.id)
+}
+
+pub fn names() -> impl Iterator<Item = &'static str> {
+    std::iter::once("air").chain(BLOCKS.iter().map(|block| block.name))
+}
+
+//Air, and any id we don't know about, is treated as empty space
+pub fn definition(id: BlockId) -> Option<&'static BlockDefinition> {
+    BLOCKS.iter().find(|block| block.id == id)
+}
+
+pub fn is_opaque(id: BlockId) -> bool {
+    definition(id).is_some_and(|block| block.opaque)
+}
+
+pub fn is_translucent(id: BlockId) -> bool {
+    definition(id).is_some_and(|block| block.translucent)
+}
+
+pub fn emission(id: BlockId) -> u8 {
+    definition(id).map_or(0, |block| block.emission)
+}
+
+//Maps every block face to a layer in the block texture array. Layers are handed out in the order
+//texture names are first seen, so the renderer and mesher agree as long as both use this registry
+pub struct BlockRegistry {
+    texture_names: Vec<&'static str>,
+    face_layers: HashMap<(BlockId, Face), u32>
+}
+
+impl BlockRegistry {
+    pub fn new() -> Self {
+        let mut texture_names: Vec<&'static str> = Vec::new();
+        let mut face_layers = HashMap::new();
+
+        for block in BLOCKS {
+            debug!("Registering block {} with id {}.", block.name, block.id);
+            for face in Face::ALL {
+                let name = block.textures.for_face(face);
+                let layer = match texture_names.iter().position(|&existing| existing == name) {
+                    Some(layer) => layer,
+                    None => {
+                        texture_names.push(name);
+                        texture_names.len() - 1
+                    }
+                };
+                face_layers.insert((block.id, face), layer as u32);
+            }
+        }
+
+        Self {
+            texture_names,
+            face_layers
+        }
+    }
+
+    pub fn texture_names(&self) -> &[&'static str] {
+        &self.texture_names
+    }
+
+    //Unknown blocks fall back to layer 0 rather than failing to mesh
+    pub fn face_layer(&self, block: BlockId, face: Face) -> u32 {
+        self.face_layers.get(&(block, face)).copied().unwrap_or(0)
+    }
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,25 @@
+use glam::Vec3;
 use noise::{Perlin, NoiseFn};
 
 use super::ChunkPos;
+use super::block::{DIRT, GRASS, STONE};
 
 pub const VOXEL_SIZE: f32 = 0.1;
 pub const CHUNK_SIZE: usize = 16;
+//Layers of dirt between the grass and the stone
+const DIRT_DEPTH: usize = 3;
 
 
 pub type ChunkBlockData = [[[u8; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]; 
 
+//The world space corners of a chunk. Voxels are centred on their grid position, so a chunk starts
+//half a voxel before its origin
+pub fn chunk_bounds(pos: ChunkPos) -> (Vec3, Vec3) {
+    let chunk_length = CHUNK_SIZE as f32 * VOXEL_SIZE;
+    let min = Vec3::new(pos.0 as f32, pos.1 as f32, pos.2 as f32) * chunk_length - Vec3::splat(VOXEL_SIZE * 0.5);
+    (min, min + Vec3::splat(chunk_length))
+}
+
 pub struct Chunk {
     //Give me an array of the size of the chunk (x) containing an array of the size of the chunk (y)
     //containing an array the size of the chunk (z) of u8s (block IDs)
@@ -29,9 +41,18 @@ impl Chunk {
                 let height = ((perlin.get([nx, nz]) + 1.0) * 0.5 * CHUNK_SIZE as f64 * 0.5) as usize;
 
                 for y in 0..CHUNK_SIZE {
-                    if y <= height {
-                        blocks[x][y][z] = 1;
+                    if y > height {
+                        continue;
                     }
+
+                    //Grass on the surface, a few layers of dirt, then stone all the way down
+                    blocks[x][y][z] = if y == height {
+                        GRASS
+                    } else if y + DIRT_DEPTH >= height {
+                        DIRT
+                    } else {
+                        STONE
+                    };
                 }
             }
         }
@@ -47,7 +68,7 @@ impl Chunk {
         for x in 0..CHUNK_SIZE {
             for z in 0..CHUNK_SIZE {
                 for y in 0..1 {
-                    blocks[x][y][z] = 1; //Call this solid ground for now
+                    blocks[x][y][z] = GRASS;
                 }
             }
         }
@@ -1,45 +1,106 @@
 use crate::events::{Event, EventHandler, EventType};
 use crate::world::{ChunkPos, chunk::ChunkBlockData};
-use crate::rendering::mesh::Mesh;
-use crate::{VOXEL_SIZE, CHUNK_SIZE};
+use crate::world::light::{PaddedLight, MAX_LIGHT};
+use crate::rendering::mesh::{Mesh, VertexAttribute, VertexLayout};
+use crate::world::block::{self, BlockRegistry, Face, AIR};
+use crate::world::chunk::{VOXEL_SIZE, CHUNK_SIZE};
 use std::collections::HashMap;
+use std::rc::Rc;
 use glam::{Mat4, Vec3};
 use crate::rendering::render_context::RenderMesh;
-use tracing::debug;
+use tracing::{debug, warn};
+use crate::debug::profiler;
+use crate::error::report;
+use crate::rendering::error::GpuError;
 
 pub struct ChunkMeshManager {
     meshes: HashMap<ChunkPos, RenderMesh>,
+    //Only chunks with translucent blocks in them have an entry here
+    translucent_meshes: HashMap<ChunkPos, RenderMesh>,
+    blocks: Rc<BlockRegistry>
 }
 
-const CUBE_VERTICES: &[f32] = &[
-    -1.0, -1.0, -1.0,
-     1.0, -1.0, -1.0,
-     1.0,  1.0, -1.0,
-    -1.0,  1.0, -1.0,
-    -1.0, -1.0,  1.0,
-     1.0, -1.0,  1.0,
-     1.0,  1.0,  1.0,
-    -1.0,  1.0,  1.0
-];
-
-const CUBE_INDICES: &[u32] = &[
-    0,1,2,2,3,0, //Back
-    4,5,6,6,7,4, //Front
-    4,0,3,3,7,4, //Left
-    1,5,6,6,2,1, //Right
-    3,2,6,6,7,3, //Top
-    0,1,5,5,4,0, //Bottom
-];
+//Position, UV, which layer of the block texture array to sample, the face normal, then the sky and
+//block light in front of the face from 0 to 1
+pub const CHUNK_VERTEX_LAYOUT: VertexLayout = VertexLayout {
+    name: "chunk",
+    attributes: &[
+        VertexAttribute { name: "pos", location: 0, components: 3 },
+        VertexAttribute { name: "uv", location: 1, components: 2 },
+        VertexAttribute { name: "layer", location: 2, components: 1 },
+        VertexAttribute { name: "normal", location: 3, components: 3 },
+        VertexAttribute { name: "light", location: 4, components: 2 },
+    ]
+};
+
+//The CPU side of a chunk mesh, before it is uploaded to OpenGL
+pub struct ChunkMeshData {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>
+}
+
+//Opaque and cutout faces are drawn first, translucent faces are blended over them afterwards
+pub struct ChunkMeshes {
+    pub opaque: ChunkMeshData,
+    pub translucent: ChunkMeshData
+}
+
+//Corners of each face on a unit cube, ordered bottom-left, bottom-right, top-right, top-left as
+//seen from outside the cube, so both triangles wind counter-clockwise
+fn face_corners(face: Face) -> [(f32, f32, f32); 4] {
+    match face {
+        Face::Top => [(0.0, 1.0, 1.0), (1.0, 1.0, 1.0), (1.0, 1.0, 0.0), (0.0, 1.0, 0.0)],
+        Face::Bottom => [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (1.0, 0.0, 1.0), (0.0, 0.0, 1.0)],
+        Face::North => [(1.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 1.0, 0.0), (1.0, 1.0, 0.0)],
+        Face::South => [(0.0, 0.0, 1.0), (1.0, 0.0, 1.0), (1.0, 1.0, 1.0), (0.0, 1.0, 1.0)],
+        Face::East => [(1.0, 0.0, 1.0), (1.0, 0.0, 0.0), (1.0, 1.0, 0.0), (1.0, 1.0, 1.0)],
+        Face::West => [(0.0, 0.0, 0.0), (0.0, 0.0, 1.0), (0.0, 1.0, 1.0), (0.0, 1.0, 0.0)],
+    }
+}
+
+//Image rows run top to bottom, so the top of each face samples v = 0
+const FACE_UVS: [(f32, f32); 4] = [(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)];
+const FACE_INDICES: [u32; 6] = [0, 1, 2, 2, 3, 0];
 
 impl ChunkMeshManager {
-    pub fn new() -> Self {
+    pub fn new(blocks: Rc<BlockRegistry>) -> Self {
         Self {
-            meshes: HashMap::new()
+            meshes: HashMap::new(),
+            translucent_meshes: HashMap::new(),
+            blocks
         }
     }
 
-    pub fn get_or_create(&mut self, pos: ChunkPos, blocks: &ChunkBlockData) {
-        self.meshes.entry(pos).or_insert_with(|| generate_mesh(pos, blocks));
+    pub fn get_or_create(&mut self, pos: ChunkPos, blocks: &ChunkBlockData, light: &PaddedLight) {
+        if !self.meshes.contains_key(&pos) {
+            self.regenerate(pos, blocks, light);
+        }
+    }
+
+    //A chunk whose mesh can't be uploaded is left without one, rather than showing a stale mesh
+    pub fn regenerate(&mut self, pos: ChunkPos, blocks: &ChunkBlockData, light: &PaddedLight) {
+        let (opaque, translucent) = match generate_mesh(pos, blocks, light, &self.blocks) {
+            Ok(meshes) => meshes,
+            Err(error) => {
+                warn!("Unable to upload the mesh for chunk ({}, {}, {}): {}", pos.0, pos.1, pos.2, report(&error));
+                self.remove(pos);
+                return;
+            }
+        };
+        self.meshes.insert(pos, opaque);
+        match translucent {
+            Some(translucent) => self.translucent_meshes.insert(pos, translucent),
+            None => self.translucent_meshes.remove(&pos),
+        };
+    }
+
+    pub fn remove(&mut self, pos: ChunkPos) {
+        self.meshes.remove(&pos);
+        self.translucent_meshes.remove(&pos);
+    }
+
+    pub fn has_mesh(&self, pos: ChunkPos) -> bool {
+        self.meshes.contains_key(&pos)
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&ChunkPos, &RenderMesh)> {
@@ -53,56 +114,128 @@ impl ChunkMeshManager {
         }
         meshes
     }
+
+    pub fn translucent_meshes(&self) -> Vec<&RenderMesh> {
+        self.translucent_meshes.values().collect()
+    }
 }
 
 impl EventHandler for ChunkMeshManager {
     fn on_event(&mut self, event: &Event) {
-        if let Event::ChunkLoaded(pos, blocks) = event {
+        if let Event::ChunkLoaded(pos, blocks, light) = event {
             debug!("ChunkLoaded event received - generating mesh...");
-            self.get_or_create(*pos, blocks);
+            self.get_or_create(*pos, blocks, light);
+        } else if let Event::ChunkModified(pos, blocks, light) = event {
+            debug!("ChunkModified event received - regenerating mesh...");
+            self.regenerate(*pos, blocks, light);
         } else if let Event::ChunkUnloaded(pos) = event {
             debug!("ChunkUnloaded event received - removing mesh...");
-            self.meshes.remove(pos);
+            self.remove(*pos);
         }
     }
 
     fn event_types(&self) -> Vec<EventType> {
-        let mut events = Vec::new();
-        events.push(EventType::ChunkLoaded);
-        events.push(EventType::ChunkUnloaded);
-        events
+        vec![EventType::ChunkLoaded, EventType::ChunkModified, EventType::ChunkUnloaded]
     }
 }
 
-fn generate_mesh(pos: ChunkPos, blocks: &ChunkBlockData) -> RenderMesh {
+fn generate_mesh(pos: ChunkPos, blocks: &ChunkBlockData, light: &PaddedLight, registry: &BlockRegistry) -> Result<(RenderMesh, Option<RenderMesh>), GpuError> {
     debug!("Generating mesh at ({}, {}, {})...", pos.0, pos.1, pos.2);
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
+    let data = {
+        let _scope = profiler::scope("mesh_build");
+        build_chunk_mesh(blocks, light, registry)
+    };
+    let _scope = profiler::scope("mesh_upload");
+    let model = model_for_chunk(pos);
+    let center = model.transform_point3(Vec3::splat(CHUNK_SIZE as f32 * VOXEL_SIZE / 2.0));
+    let upload = |data: &ChunkMeshData| Ok(RenderMesh {
+        model,
+        center,
+        mesh: Mesh::from_vertices_and_indices(&data.vertices, &data.indices, &CHUNK_VERTEX_LAYOUT)?
+    });
+
+    let translucent = if data.translucent.indices.is_empty() {
+        None
+    } else {
+        Some(upload(&data.translucent)?)
+    };
+    Ok((upload(&data.opaque)?, translucent))
+}
+
+//Emits one quad per block face that isn't hidden by a solid neighbour in the same chunk. Faces on
+//the chunk border are always emitted, since we can't see into the neighbouring chunk from here.
+//Each face takes the light of the voxel in front of it
+pub fn build_chunk_mesh(blocks: &ChunkBlockData, light: &PaddedLight, registry: &BlockRegistry) -> ChunkMeshes {
+    let mut opaque = ChunkMeshData { vertices: Vec::new(), indices: Vec::new() };
+    let mut translucent = ChunkMeshData { vertices: Vec::new(), indices: Vec::new() };
+    let floats_per_vertex = CHUNK_VERTEX_LAYOUT.floats_per_vertex();
 
     for x in 0..CHUNK_SIZE {
         for y in 0..CHUNK_SIZE {
             for z in 0..CHUNK_SIZE {
-               //If air then just continue
-                if blocks[x][y][z] == 0 {
+                let block = blocks[x][y][z];
+                if block == AIR {
                     continue;
                 }
 
-                //Otherwise lets make a mesh for the chunk
-                //Right now this is just a terrible implementation as Proof of Concept
-                let base_index = (vertices.len() / 3) as u32;
-                let cube = unit_cube_vertices((x, y, z));
-                vertices.extend_from_slice(&cube);
-                indices.extend_from_slice(&unit_cube_indices(base_index));
+                for face in Face::ALL {
+                    if is_face_hidden(blocks, (x, y, z), face) {
+                        continue;
+                    }
+
+                    let ChunkMeshData { vertices, indices } = if block::is_translucent(block) {
+                        &mut translucent
+                    } else {
+                        &mut opaque
+                    };
+                    let base_index = (vertices.len() / floats_per_vertex) as u32;
+                    let layer = registry.face_layer(block, face) as f32;
+                    let normal = face.normal();
+                    let (sky, block_light) = light.get(x as i32 + normal.0, y as i32 + normal.1, z as i32 + normal.2);
+
+                    for (corner, uv) in face_corners(face).iter().zip(FACE_UVS.iter()) {
+                        //Voxels are centred on their grid position, as they always have been
+                        vertices.extend_from_slice(&[
+                            (x as f32 + corner.0 - 0.5) * VOXEL_SIZE,
+                            (y as f32 + corner.1 - 0.5) * VOXEL_SIZE,
+                            (z as f32 + corner.2 - 0.5) * VOXEL_SIZE,
+                            uv.0,
+                            uv.1,
+                            layer,
+                            normal.0 as f32,
+                            normal.1 as f32,
+                            normal.2 as f32,
+                            sky as f32 / MAX_LIGHT as f32,
+                            block_light as f32 / MAX_LIGHT as f32
+                        ]);
+                    }
+
+                    indices.extend(FACE_INDICES.iter().map(|i| base_index + i));
+                }
             }
         }
     }
 
-    RenderMesh{
-        model: model_for_chunk(pos),
-        mesh: Mesh::from_vertices_and_indices(&vertices, &indices)
+    ChunkMeshes {
+        opaque,
+        translucent
     }
 }
 
+fn is_face_hidden(blocks: &ChunkBlockData, (x, y, z): (usize, usize, usize), face: Face) -> bool {
+    let (dx, dy, dz) = face.normal();
+    let (nx, ny, nz) = (x as i32 + dx, y as i32 + dy, z as i32 + dz);
+    let range = 0..CHUNK_SIZE as i32;
+
+    if !range.contains(&nx) || !range.contains(&ny) || !range.contains(&nz) {
+        return false;
+    }
+
+    //Faces between two of the same see-through block, like inside a body of water, are never seen
+    let neighbour = blocks[nx as usize][ny as usize][nz as usize];
+    block::is_opaque(neighbour) || neighbour == blocks[x][y][z]
+}
+
 pub fn model_for_chunk(pos: ChunkPos) -> Mat4 {
     Mat4::from_translation(
         Vec3::new(
@@ -113,26 +246,34 @@ pub fn model_for_chunk(pos: ChunkPos) -> Mat4 {
     )
 }
 
-fn unit_cube_vertices(offset: (usize, usize, usize)) -> [f32; 3*8] {
-    let mut vertices = [0.0; 24];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::gpu::{self, GpuResourceKind};
+    use crate::rendering::headless::HeadlessContext;
+    use crate::world::block::{STONE, WATER};
+    use crate::world::light::LightMap;
 
-    //Every slice is a vertex in the VBO
-    for (i, vertex) in CUBE_VERTICES.chunks(3).enumerate() {
-        let base = i* 3;
-        vertices[base + 0] = (vertex[0] * VOXEL_SIZE/2.0) + (offset.0 as f32 * VOXEL_SIZE);
-        vertices[base + 1] = (vertex[1] * VOXEL_SIZE/2.0) + (offset.1 as f32 * VOXEL_SIZE);
-        vertices[base + 2] = (vertex[2] * VOXEL_SIZE/2.0) + (offset.2 as f32 * VOXEL_SIZE);
-    }
-
-    return vertices;
-}
+    #[test]
+    #[ignore = "needs a display with OpenGL 3.3"]
+    fn unloading_a_chunk_frees_its_meshes() {
+        let _context = HeadlessContext::new().expect("needs a display with OpenGL 3.3");
+        let mut manager = ChunkMeshManager::new(Rc::new(BlockRegistry::new()));
+        let mut blocks = [[[AIR; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+        blocks[0][0][0] = STONE;
+        blocks[1][0][0] = WATER;
+        let light = LightMap::new().padded((0, 0, 0));
 
-fn unit_cube_indices(base_index: u32) -> [u32; 36] {
-    let mut indices = [0u32; 36];
+        let before = gpu::live_counts();
+        manager.on_event(&Event::ChunkLoaded((0, 0, 0), blocks, light));
+        //An opaque and a translucent mesh, each with a VAO and its vertex and index buffers
+        let loaded = gpu::live_counts();
+        assert_eq!(loaded.get(GpuResourceKind::VertexArray), before.get(GpuResourceKind::VertexArray) + 2);
+        assert_eq!(loaded.get(GpuResourceKind::Buffer), before.get(GpuResourceKind::Buffer) + 4);
 
-    for (i, &idx) in CUBE_INDICES.iter().enumerate() {
-        indices[i] = base_index + idx;
+        manager.on_event(&Event::ChunkUnloaded((0, 0, 0)));
+        assert!(!manager.has_mesh((0, 0, 0)));
+        assert!(manager.translucent_meshes().is_empty());
+        assert!(gpu::live_counts().since(&before).iter().all(|&(_, change)| change == 0));
     }
-
-    return indices;
 }
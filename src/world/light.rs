@@ -0,0 +1,462 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::debug;
+use super::{ChunkMap, ChunkPos};
+use super::block::{self, BlockId};
+use super::chunk::CHUNK_SIZE;
+
+pub const MAX_LIGHT: u8 = 15;
+
+//A voxel position in world block coordinates, rather than chunk coordinates
+pub type BlockPos = (i32, i32, i32);
+
+//Sky light in the high nibble, block light in the low nibble
+type ChunkLightData = [[[u8; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE];
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+const UP: (i32, i32, i32) = (0, 1, 0);
+const DOWN: (i32, i32, i32) = (0, -1, 0);
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum LightChannel {
+    Sky,
+    Block
+}
+
+impl LightChannel {
+    const ALL: [LightChannel; 2] = [LightChannel::Sky, LightChannel::Block];
+
+    fn unpack(&self, packed: u8) -> u8 {
+        match self {
+            LightChannel::Sky => packed >> 4,
+            LightChannel::Block => packed & 0x0F,
+        }
+    }
+
+    fn pack(&self, packed: u8, level: u8) -> u8 {
+        match self {
+            LightChannel::Sky => (packed & 0x0F) | (level << 4),
+            LightChannel::Block => (packed & 0xF0) | level,
+        }
+    }
+
+    //Full sky light falls straight down without fading, everything else loses a level per block
+    fn spread(&self, level: u8, direction: (i32, i32, i32)) -> u8 {
+        if *self == LightChannel::Sky && direction == DOWN && level == MAX_LIGHT {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(1)
+        }
+    }
+}
+
+pub fn split_block_pos(pos: BlockPos) -> (ChunkPos, (usize, usize, usize)) {
+    let size = CHUNK_SIZE as i32;
+    (
+        (pos.0.div_euclid(size), pos.1.div_euclid(size), pos.2.div_euclid(size)),
+        (pos.0.rem_euclid(size) as usize, pos.1.rem_euclid(size) as usize, pos.2.rem_euclid(size) as usize)
+    )
+}
+
+pub fn block_pos(chunk: ChunkPos, (x, y, z): (usize, usize, usize)) -> BlockPos {
+    let size = CHUNK_SIZE as i32;
+    (chunk.0 * size + x as i32, chunk.1 * size + y as i32, chunk.2 * size + z as i32)
+}
+
+fn offset(pos: BlockPos, direction: (i32, i32, i32)) -> BlockPos {
+    (pos.0 + direction.0, pos.1 + direction.1, pos.2 + direction.2)
+}
+
+fn block_at(chunks: &ChunkMap, pos: BlockPos) -> Option<BlockId> {
+    let (chunk, (x, y, z)) = split_block_pos(pos);
+    chunks.get(&chunk).map(|chunk| chunk.blocks[x][y][z])
+}
+
+//A chunk's light plus a one block border taken from its neighbours, so faces on the edge of a
+//chunk can be lit by the voxel in front of them
+pub struct PaddedLight {
+    data: Vec<u8>
+}
+
+impl PaddedLight {
+    const SIZE: usize = CHUNK_SIZE + 2;
+
+    //Coordinates are local to the chunk and run from -1 to CHUNK_SIZE
+    pub fn get(&self, x: i32, y: i32, z: i32) -> (u8, u8) {
+        let index = |v: i32| (v + 1) as usize;
+        let packed = self.data[(index(x) * Self::SIZE + index(y)) * Self::SIZE + index(z)];
+        (LightChannel::Sky.unpack(packed), LightChannel::Block.unpack(packed))
+    }
+}
+
+//Per voxel sky light and block light for every loaded chunk. Sky light pours down from open sky
+//and block light spreads out from emissive blocks, both by breadth first flood fill that crosses
+//chunk borders. Every update returns the chunks whose light changed so they can be remeshed
+pub struct LightMap {
+    chunks: HashMap<ChunkPos, ChunkLightData>
+}
+
+impl LightMap {
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new()
+        }
+    }
+
+    pub fn get(&self, pos: BlockPos, channel: LightChannel) -> Option<u8> {
+        let (chunk, (x, y, z)) = split_block_pos(pos);
+        self.chunks.get(&chunk).map(|light| channel.unpack(light[x][y][z]))
+    }
+
+    fn set(&mut self, pos: BlockPos, channel: LightChannel, level: u8) {
+        let (chunk, (x, y, z)) = split_block_pos(pos);
+        if let Some(light) = self.chunks.get_mut(&chunk) {
+            light[x][y][z] = channel.pack(light[x][y][z], level);
+        }
+    }
+
+    //Unloaded space counts as open sky with no block light
+    pub fn padded(&self, chunk: ChunkPos) -> PaddedLight {
+        let size = CHUNK_SIZE as i32;
+        let mut data = Vec::with_capacity(PaddedLight::SIZE.pow(3));
+        for x in -1..=size {
+            for y in -1..=size {
+                for z in -1..=size {
+                    let pos = block_pos(chunk, (0, 0, 0));
+                    let pos = (pos.0 + x, pos.1 + y, pos.2 + z);
+                    let sky = self.get(pos, LightChannel::Sky).unwrap_or(MAX_LIGHT);
+                    let block = self.get(pos, LightChannel::Block).unwrap_or(0);
+                    data.push(LightChannel::Block.pack(LightChannel::Sky.pack(0, sky), block));
+                }
+            }
+        }
+
+        PaddedLight { data }
+    }
+
+    //Lights a freshly loaded chunk, pulling in light from its neighbours and pushing its own out
+    pub fn light_chunk(&mut self, chunks: &ChunkMap, pos: ChunkPos) -> HashSet<ChunkPos> {
+        let mut changed = HashSet::new();
+        let Some(chunk) = chunks.get(&pos) else {
+            return changed;
+        };
+
+        debug!("Lighting chunk at ({}, {}, {})...", pos.0, pos.1, pos.2);
+        self.chunks.insert(pos, [[[0; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE]);
+        changed.insert(pos);
+
+        let mut sky_queue = VecDeque::new();
+        let mut block_queue = VecDeque::new();
+
+        //Columns start lit if the chunk above passes full sky light down, or isn't loaded at all
+        let above = (pos.0, pos.1 + 1, pos.2);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let open = match self.chunks.get(&above) {
+                    Some(light) => LightChannel::Sky.unpack(light[x][0][z]) == MAX_LIGHT,
+                    None => true
+                };
+                if !open {
+                    continue;
+                }
+
+                for y in (0..CHUNK_SIZE).rev() {
+                    if block::is_opaque(chunk.blocks[x][y][z]) {
+                        break;
+                    }
+                    let voxel = block_pos(pos, (x, y, z));
+                    self.set(voxel, LightChannel::Sky, MAX_LIGHT);
+                    sky_queue.push_back(voxel);
+                }
+            }
+        }
+
+        for x in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let emission = block::emission(chunk.blocks[x][y][z]);
+                    if emission > 0 {
+                        let voxel = block_pos(pos, (x, y, z));
+                        self.set(voxel, LightChannel::Block, emission);
+                        block_queue.push_back(voxel);
+                    }
+                }
+            }
+        }
+
+        //Whatever the neighbours already hold floods in across the shared faces
+        let size = CHUNK_SIZE as i32;
+        for direction in DIRECTIONS {
+            for a in 0..size {
+                for b in 0..size {
+                    let local = match direction {
+                        (1, _, _) => (size, a, b),
+                        (-1, _, _) => (-1, a, b),
+                        (_, 1, _) => (a, size, b),
+                        (_, -1, _) => (a, -1, b),
+                        (_, _, 1) => (a, b, size),
+                        _ => (a, b, -1),
+                    };
+                    let origin = block_pos(pos, (0, 0, 0));
+                    let voxel = (origin.0 + local.0, origin.1 + local.1, origin.2 + local.2);
+                    sky_queue.push_back(voxel);
+                    block_queue.push_back(voxel);
+                }
+            }
+        }
+
+        //The chunk below was lit as if it were open to the sky. Take that back for columns this
+        //chunk now blocks
+        let below = (pos.0, pos.1 - 1, pos.2);
+        if self.chunks.contains_key(&below) {
+            let mut removals = Vec::new();
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let top = block_pos(below, (x, CHUNK_SIZE - 1, z));
+                    let ours = self.get(block_pos(pos, (x, 0, z)), LightChannel::Sky);
+                    if ours != Some(MAX_LIGHT) && self.get(top, LightChannel::Sky) == Some(MAX_LIGHT) {
+                        self.set(top, LightChannel::Sky, 0);
+                        removals.push((top, MAX_LIGHT));
+                    }
+                }
+            }
+            changed.insert(below);
+            sky_queue.extend(self.remove(chunks, LightChannel::Sky, removals, &mut changed));
+        }
+
+        self.propagate(chunks, LightChannel::Sky, sky_queue, &mut changed);
+        self.propagate(chunks, LightChannel::Block, block_queue, &mut changed);
+        changed
+    }
+
+    //Light spread into the chunks that remain is left as it is until they are reloaded
+    pub fn retain(&mut self, loaded: &HashSet<ChunkPos>) {
+        self.chunks.retain(|pos, _| loaded.contains(pos));
+    }
+
+    //Call after the block at pos has been changed to new in chunks
+    pub fn on_block_changed(&mut self, chunks: &ChunkMap, pos: BlockPos, new: BlockId) -> HashSet<ChunkPos> {
+        let mut changed = HashSet::new();
+
+        for channel in LightChannel::ALL {
+            let Some(current) = self.get(pos, channel) else {
+                return changed;
+            };
+
+            //Clear the voxel and anything lit through it, then refill from whatever remains
+            let mut relight = VecDeque::new();
+            if current > 0 {
+                self.set(pos, channel, 0);
+                changed.insert(split_block_pos(pos).0);
+                relight.extend(self.remove(chunks, channel, vec![(pos, current)], &mut changed));
+            }
+
+            if channel == LightChannel::Block && block::emission(new) > 0 {
+                self.set(pos, channel, block::emission(new));
+                changed.insert(split_block_pos(pos).0);
+                relight.push_back(pos);
+            }
+
+            //Nothing above to pour it in when the chunk above isn't loaded, which counts as open sky
+            //the same as when the chunk was first lit
+            if channel == LightChannel::Sky && !block::is_opaque(new) && self.get(offset(pos, UP), channel).is_none() {
+                self.set(pos, channel, MAX_LIGHT);
+                changed.insert(split_block_pos(pos).0);
+                relight.push_back(pos);
+            }
+
+            if !block::is_opaque(new) {
+                relight.extend(DIRECTIONS.iter().map(|&direction| offset(pos, direction)));
+            }
+
+            self.propagate(chunks, channel, relight, &mut changed);
+        }
+
+        changed
+    }
+
+    fn propagate(&mut self, chunks: &ChunkMap, channel: LightChannel, mut queue: VecDeque<BlockPos>, changed: &mut HashSet<ChunkPos>) {
+        while let Some(pos) = queue.pop_front() {
+            let Some(level) = self.get(pos, channel) else {
+                continue;
+            };
+            if level <= 1 {
+                continue;
+            }
+
+            for direction in DIRECTIONS {
+                let next = offset(pos, direction);
+                match block_at(chunks, next) {
+                    Some(block) if !block::is_opaque(block) => {}
+                    _ => continue,
+                }
+
+                //Chunks that are loaded but not lit yet get seeded from their borders later
+                let next_level = channel.spread(level, direction);
+                if self.get(next, channel).is_some_and(|existing| existing < next_level) {
+                    self.set(next, channel, next_level);
+                    changed.insert(split_block_pos(next).0);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    //Takes already cleared voxels with the level they used to hold, clears everything that was lit
+    //through them, and returns the brighter voxels bordering the dark region to relight from
+    fn remove(&mut self, chunks: &ChunkMap, channel: LightChannel, seeds: Vec<(BlockPos, u8)>, changed: &mut HashSet<ChunkPos>) -> VecDeque<BlockPos> {
+        let mut queue: VecDeque<(BlockPos, u8)> = seeds.into();
+        let mut relight = VecDeque::new();
+
+        while let Some((pos, old)) = queue.pop_front() {
+            for direction in DIRECTIONS {
+                let next = offset(pos, direction);
+                let Some(level) = self.get(next, channel) else {
+                    continue;
+                };
+                if level == 0 {
+                    continue;
+                }
+
+                let lit_through = level < old || (channel.spread(old, direction) == MAX_LIGHT && level == MAX_LIGHT);
+                if lit_through {
+                    self.set(next, channel, 0);
+                    changed.insert(split_block_pos(next).0);
+                    queue.push_back((next, level));
+
+                    //Emissive blocks caught in the dark region still light themselves
+                    let emission = block_at(chunks, next).map_or(0, block::emission);
+                    if channel == LightChannel::Block && emission > 0 {
+                        self.set(next, channel, emission);
+                        relight.push_back(next);
+                    }
+                } else {
+                    relight.push_back(next);
+                }
+            }
+        }
+
+        relight
+    }
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::{AIR, LAMP, STONE};
+    use crate::world::chunk::Chunk;
+
+    fn empty_chunk() -> Chunk {
+        Chunk { blocks: [[[AIR; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE] }
+    }
+
+    fn set(chunks: &mut ChunkMap, pos: BlockPos, id: BlockId) {
+        let (chunk, (x, y, z)) = split_block_pos(pos);
+        chunks.get_mut(&chunk).unwrap().blocks[x][y][z] = id;
+    }
+
+    //Lit from scratch in the given order, the way the world lights chunks as they load
+    fn lit(chunks: &ChunkMap, order: &[ChunkPos]) -> LightMap {
+        let mut light = LightMap::new();
+        for &pos in order {
+            light.light_chunk(chunks, pos);
+        }
+        light
+    }
+
+    fn assert_same_light(actual: &LightMap, expected: &LightMap, chunks: &[ChunkPos]) {
+        for &chunk in chunks {
+            for x in 0..CHUNK_SIZE {
+                for y in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        let pos = block_pos(chunk, (x, y, z));
+                        for channel in LightChannel::ALL {
+                            assert_eq!(actual.get(pos, channel), expected.get(pos, channel), "light differs at {:?}", pos);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sky_light_fills_open_columns_and_stops_under_solid_blocks() {
+        let mut chunks = ChunkMap::new();
+        chunks.insert((0, 0, 0), empty_chunk());
+        set(&mut chunks, (4, 10, 4), STONE);
+        let light = lit(&chunks, &[(0, 0, 0)]);
+
+        assert_eq!(light.get((0, 0, 0), LightChannel::Sky), Some(MAX_LIGHT));
+        assert_eq!(light.get((4, 15, 4), LightChannel::Sky), Some(MAX_LIGHT));
+        assert_eq!(light.get((4, 11, 4), LightChannel::Sky), Some(MAX_LIGHT));
+        assert_eq!(light.get((4, 10, 4), LightChannel::Sky), Some(0));
+        //Only lit from the side under the stone, so a level down from the open columns around it
+        assert_eq!(light.get((4, 9, 4), LightChannel::Sky), Some(MAX_LIGHT - 1));
+    }
+
+    #[test]
+    fn block_light_falls_off_by_one_per_block() {
+        let mut chunks = ChunkMap::new();
+        chunks.insert((0, 0, 0), empty_chunk());
+        set(&mut chunks, (8, 8, 8), LAMP);
+        let light = lit(&chunks, &[(0, 0, 0)]);
+
+        let emission = block::emission(LAMP);
+        assert_eq!(light.get((8, 8, 8), LightChannel::Block), Some(emission));
+        assert_eq!(light.get((9, 8, 8), LightChannel::Block), Some(emission - 1));
+        assert_eq!(light.get((11, 8, 8), LightChannel::Block), Some(emission - 3));
+        assert_eq!(light.get((8, 8, 12), LightChannel::Block), Some(emission - 4));
+        assert_eq!(light.get((10, 10, 10), LightChannel::Block), Some(emission - 6));
+    }
+
+    #[test]
+    fn block_changes_relight_the_same_as_lighting_from_scratch() {
+        let loaded = [(0, 0, 0)];
+        let mut chunks = ChunkMap::new();
+        chunks.insert((0, 0, 0), empty_chunk());
+        set(&mut chunks, (8, 8, 8), LAMP);
+        let mut light = lit(&chunks, &loaded);
+
+        //Placing shades a sky column and blocks the lamp on one side
+        for (pos, id) in [((3, 15, 3), STONE), ((9, 8, 8), STONE), ((4, 4, 4), LAMP)] {
+            set(&mut chunks, pos, id);
+            light.on_block_changed(&chunks, pos, id);
+            assert_same_light(&light, &lit(&chunks, &loaded), &loaded);
+        }
+
+        //Removing lets both back in, and takes the second lamp's light away with it
+        for pos in [(3, 15, 3), (9, 8, 8), (4, 4, 4)] {
+            set(&mut chunks, pos, AIR);
+            light.on_block_changed(&chunks, pos, AIR);
+            assert_same_light(&light, &lit(&chunks, &loaded), &loaded);
+        }
+    }
+
+    #[test]
+    fn light_crosses_chunk_borders() {
+        let loaded = [(0, 0, 0), (1, 0, 0)];
+        let mut chunks = ChunkMap::new();
+        chunks.insert((0, 0, 0), empty_chunk());
+        chunks.insert((1, 0, 0), empty_chunk());
+        set(&mut chunks, (15, 8, 8), LAMP);
+
+        let emission = block::emission(LAMP);
+        for order in [loaded, [(1, 0, 0), (0, 0, 0)]] {
+            let light = lit(&chunks, &order);
+            assert_eq!(light.get((16, 8, 8), LightChannel::Block), Some(emission - 1));
+            assert_eq!(light.get((18, 8, 8), LightChannel::Block), Some(emission - 3));
+        }
+
+        //A change on one side of the border relights the other
+        let mut light = lit(&chunks, &loaded);
+        set(&mut chunks, (16, 8, 8), STONE);
+        let changed = light.on_block_changed(&chunks, (16, 8, 8), STONE);
+        assert!(changed.contains(&(1, 0, 0)));
+        assert_same_light(&light, &lit(&chunks, &loaded), &loaded);
+    }
+}
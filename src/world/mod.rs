@@ -1,11 +1,18 @@
+pub mod block;
 pub mod chunk;
 pub mod chunk_mesh_manager;
+pub mod light;
+pub mod save;
+pub mod time;
 
 use tracing::debug;
 use std::collections::hash_map::Entry::Vacant;
 use crate::events::{EventQueue, Event, Event::ChunkUnloaded};
+use crate::world::block::{BlockId, AIR};
 use crate::world::chunk::Chunk;
-use crate::{VOXEL_SIZE, CHUNK_SIZE};
+use crate::world::light::{BlockPos, LightMap, split_block_pos};
+use crate::world::time::WorldClock;
+use crate::world::chunk::{VOXEL_SIZE, CHUNK_SIZE};
 use noise::Perlin;
 use std::collections::{HashMap, HashSet};
 use glam::Vec3;
@@ -13,11 +20,26 @@ use glam::Vec3;
 
 pub type ChunkPos = (i32, i32, i32);
 pub type ChunkMap = HashMap<ChunkPos, Chunk>;
-const CHUNK_DISTANCE: i32 = 3;
+//How many chunks are kept loaded in every direction around the player
+pub const DEFAULT_RENDER_DISTANCE: i32 = 3;
+pub const MAX_RENDER_DISTANCE: i32 = 8;
+//How far away in world units the player can break and place blocks
+pub const REACH: f32 = 8.0;
+
+//Where a ray stopped: the block it hit, and the empty block it passed through just before, which is
+//where a block placed against that face goes
+pub struct RaycastHit {
+    pub pos: BlockPos,
+    pub block: BlockId,
+    pub previous: BlockPos
+}
 
 pub struct World {
     pub seed: u32,
-    pub chunks: ChunkMap
+    pub chunks: ChunkMap,
+    pub light: LightMap,
+    pub clock: WorldClock,
+    pub render_distance: i32
 }
 
 pub fn world_to_chunk_pos(pos: Vec3) -> ChunkPos {
@@ -30,24 +52,26 @@ pub fn world_to_chunk_pos(pos: Vec3) -> ChunkPos {
 }
 
 //Every chunk is 16x16
-pub fn chunk_range(center: (i32, i32, i32)) -> impl Iterator<Item = (i32, i32, i32)> {
+pub fn chunk_range(center: (i32, i32, i32), distance: i32) -> impl Iterator<Item = (i32, i32, i32)> {
     let (cx, cy, cz) = center;
 
-    (cx - CHUNK_DISTANCE..=cx + CHUNK_DISTANCE)
+    (cx - distance..=cx + distance)
         .flat_map(move |x| {
-            (cy - CHUNK_DISTANCE..=cy + CHUNK_DISTANCE).flat_map(move |y| {
-                (cz - CHUNK_DISTANCE..=cz + CHUNK_DISTANCE).map(move |z| (x, y, z))
+            (cy - distance..=cy + distance).flat_map(move |y| {
+                (cz - distance..=cz + distance).map(move |z| (x, y, z))
             })
         })
 }
 
 impl World {
-    pub fn new() -> Self {
-        //TODO - make actually add seeding.
-        debug!("New world created with default seed.");
+    pub fn with_seed(seed: u32) -> Self {
+        debug!("New world created with seed {}.", seed);
         Self {
-            seed: 24601,
-            chunks: ChunkMap::new()
+            seed,
+            chunks: ChunkMap::new(),
+            light: LightMap::new(),
+            clock: WorldClock::new(),
+            render_distance: DEFAULT_RENDER_DISTANCE
         }
     }
 
@@ -55,17 +79,16 @@ impl World {
         debug!("Updating world...");
        //Generate chunks near the player based on the seed
        let center = world_to_chunk_pos(player_pos);
-       let loaded_chunks: HashSet<ChunkPos> = chunk_range(center).collect();
+       let loaded_chunks: HashSet<ChunkPos> = chunk_range(center, self.render_distance).collect();
        let perlin = Perlin::new(self.seed);
+       let mut new_chunks = Vec::new();
 
        for &pos in &loaded_chunks {
            debug!("Checking loaded chunk: ({}, {}, {})", &pos.0, &pos.1, &pos.2);
            if let Vacant(entry) = self.chunks.entry(pos) {
-               debug!("Chunk at ({}, {}, {}) is missing. Generating chunk and pushing event.", &pos.0, &pos.1, &pos.2);
-               let chunk = Chunk::from_perlin_noise(pos, &perlin);
-               let blocks = chunk.blocks.clone();
-               entry.insert(chunk);
-               event_queue.push_event(Event::ChunkLoaded(pos, blocks));
+               debug!("Chunk at ({}, {}, {}) is missing. Generating chunk.", &pos.0, &pos.1, &pos.2);
+               entry.insert(Chunk::from_perlin_noise(pos, &perlin));
+               new_chunks.push(pos);
            }
        }
 
@@ -78,5 +101,149 @@ impl World {
                 false
             }
         });
+       self.light.retain(&loaded_chunks);
+
+       //Light from the top down, so sky light falls through chunks that are already lit
+       new_chunks.sort_by_key(|pos| std::cmp::Reverse(pos.1));
+       let mut relit = HashSet::new();
+       for &pos in &new_chunks {
+           relit.extend(self.light.light_chunk(&self.chunks, pos));
+       }
+
+       for &pos in &new_chunks {
+           debug!("Pushing ChunkLoaded event for ({}, {}, {}).", &pos.0, &pos.1, &pos.2);
+           event_queue.push_event(Event::ChunkLoaded(pos, self.chunks[&pos].blocks, self.light.padded(pos)));
+       }
+
+       relit.retain(|pos| !new_chunks.contains(pos));
+       self.push_modified(relit, event_queue);
+    }
+
+    //Steps through every block the ray passes, one at a time, and returns the first that isn't air.
+    //Distances are in world units
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec3::ZERO {
+            return None;
+        }
+
+        //Work in blocks, where every voxel is one unit across. Voxels are centred on their grid
+        //position, so shift by half a block to put block boundaries on whole numbers
+        let start = origin / VOXEL_SIZE + Vec3::splat(0.5);
+        let max_distance = max_distance / VOXEL_SIZE;
+        let mut pos = start.floor().as_ivec3();
+        let step = direction.signum().as_ivec3();
+
+        //How far along the ray each axis next crosses a block boundary, and how far apart crossings are
+        let next_boundary = |axis: usize| {
+            let offset = if direction[axis] > 0.0 { pos[axis] as f32 + 1.0 - start[axis] } else { start[axis] - pos[axis] as f32 };
+            if direction[axis] == 0.0 { f32::INFINITY } else { offset / direction[axis].abs() }
+        };
+        let mut crossing = Vec3::new(next_boundary(0), next_boundary(1), next_boundary(2));
+        let spacing = Vec3::ONE / direction.abs();
+
+        let mut distance = 0.0;
+        let mut previous = (pos.x, pos.y, pos.z);
+        while distance <= max_distance {
+            let block_pos = (pos.x, pos.y, pos.z);
+            match self.block(block_pos) {
+                Some(AIR) | None => {}
+                Some(block) => return Some(RaycastHit { pos: block_pos, block, previous })
+            }
+            previous = block_pos;
+
+            let axis = crossing.min_position();
+            distance = crossing[axis];
+            crossing[axis] += spacing[axis];
+            pos[axis] += step[axis];
+        }
+        None
+    }
+
+    pub fn block(&self, pos: BlockPos) -> Option<BlockId> {
+        let (chunk, (x, y, z)) = split_block_pos(pos);
+        self.chunks.get(&chunk).map(|chunk| chunk.blocks[x][y][z])
+    }
+
+    //Returns false if the block's chunk isn't loaded
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockId, event_queue: &mut EventQueue) -> bool {
+        let (chunk_pos, (x, y, z)) = split_block_pos(pos);
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
+            return false;
+        };
+        if chunk.blocks[x][y][z] == block {
+            return true;
+        }
+
+        debug!("Setting block at ({}, {}, {}) to {}.", pos.0, pos.1, pos.2, block);
+        chunk.blocks[x][y][z] = block;
+
+        let mut modified = self.light.on_block_changed(&self.chunks, pos, block);
+        modified.insert(chunk_pos);
+        self.push_modified(modified, event_queue);
+        true
+    }
+
+    fn push_modified(&self, modified: HashSet<ChunkPos>, event_queue: &mut EventQueue) {
+        for pos in modified {
+            if let Some(chunk) = self.chunks.get(&pos) {
+                debug!("Pushing ChunkModified event for ({}, {}, {}).", &pos.0, &pos.1, &pos.2);
+                event_queue.push_event(Event::ChunkModified(pos, chunk.blocks, self.light.padded(pos)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::STONE;
+
+    //One empty chunk at the origin with a single stone block in it
+    fn world_with_stone(pos: BlockPos) -> World {
+        let mut world = World::with_seed(0);
+        let mut chunk = Chunk { blocks: [[[AIR; CHUNK_SIZE]; CHUNK_SIZE]; CHUNK_SIZE] };
+        chunk.blocks[pos.0 as usize][pos.1 as usize][pos.2 as usize] = STONE;
+        world.chunks.insert((0, 0, 0), chunk);
+        world
+    }
+
+    //World space centre of a block
+    fn center(pos: BlockPos) -> Vec3 {
+        Vec3::new(pos.0 as f32, pos.1 as f32, pos.2 as f32) * VOXEL_SIZE
+    }
+
+    #[test]
+    fn raycast_hits_the_first_solid_block_and_the_empty_one_before_it() {
+        let world = world_with_stone((5, 5, 5));
+        let hit = world.raycast(center((2, 5, 5)), Vec3::X, REACH).unwrap();
+        assert_eq!(hit.pos, (5, 5, 5));
+        assert_eq!(hit.block, STONE);
+        assert_eq!(hit.previous, (4, 5, 5));
+
+        let hit = world.raycast(center((5, 9, 5)), Vec3::NEG_Y, REACH).unwrap();
+        assert_eq!(hit.pos, (5, 5, 5));
+        assert_eq!(hit.previous, (5, 6, 5));
+    }
+
+    #[test]
+    fn raycast_uses_voxels_centred_on_their_grid_position() {
+        let world = world_with_stone((5, 5, 5));
+        //Just inside the stone's low x face, which is half a voxel below its centre
+        let origin = center((5, 2, 5)) - Vec3::new(VOXEL_SIZE * 0.4, 0.0, 0.0);
+        let hit = world.raycast(origin, Vec3::Y, REACH).unwrap();
+        assert_eq!(hit.pos, (5, 5, 5));
+
+        //Just outside it, in the column of blocks next to it
+        let origin = center((5, 2, 5)) - Vec3::new(VOXEL_SIZE * 0.6, 0.0, 0.0);
+        assert!(world.raycast(origin, Vec3::Y, REACH).is_none());
+    }
+
+    #[test]
+    fn raycast_misses_blocks_out_of_reach_or_behind() {
+        let world = world_with_stone((10, 5, 5));
+        assert!(world.raycast(center((2, 5, 5)), Vec3::X, 0.5).is_none());
+        assert!(world.raycast(center((2, 5, 5)), Vec3::NEG_X, REACH).is_none());
+        assert!(world.raycast(center((2, 5, 5)), Vec3::ZERO, REACH).is_none());
     }
 }
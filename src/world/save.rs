@@ -0,0 +1,121 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use glam::Vec3;
+use thiserror::Error;
+use tracing::debug;
+use super::time::WorldClock;
+
+pub const SAVE_DIRECTORY: &str = "saves";
+const SAVE_EXTENSION: &str = "sav";
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Unable to create save directory {}", .path.display())]
+    CreateDirectory { path: PathBuf, #[source] source: io::Error },
+    #[error("Unable to write save {}", .path.display())]
+    Write { path: PathBuf, #[source] source: io::Error },
+    #[error("Unable to read save {}", .path.display())]
+    Read { path: PathBuf, #[source] source: io::Error },
+    #[error("Save {} has no valid seed", .path.display())]
+    MissingSeed { path: PathBuf }
+}
+
+//Chunks are regenerated from the seed, so for now a save is just the seed and where the player was
+pub struct SaveGame {
+    pub seed: u32,
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub time_of_day: f32
+}
+
+impl SaveGame {
+    pub fn path(&self) -> PathBuf {
+        Path::new(SAVE_DIRECTORY).join(format!("world_{}.{}", self.seed, SAVE_EXTENSION))
+    }
+
+    pub fn write(&self) -> Result<PathBuf, SaveError> {
+        let path = self.path();
+        debug!("Writing save to {}...", path.display());
+
+        fs::create_dir_all(SAVE_DIRECTORY).map_err(|source| SaveError::CreateDirectory { path: PathBuf::from(SAVE_DIRECTORY), source })?;
+
+        let contents = format!(
+            "seed={}\nposition={},{},{}\nyaw={}\npitch={}\ntime={}\n",
+            self.seed,
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.yaw,
+            self.pitch,
+            self.time_of_day
+        );
+
+        match fs::write(&path, contents) {
+            Ok(()) => Ok(path),
+            Err(source) => Err(SaveError::Write { path, source })
+        }
+    }
+
+    pub fn read<T: AsRef<Path>>(path: T) -> Result<SaveGame, SaveError> {
+        let path = path.as_ref();
+        debug!("Reading save from {}...", path.display());
+
+        let contents = fs::read_to_string(path).map_err(|source| SaveError::Read { path: path.to_path_buf(), source })?;
+
+        let mut seed = None;
+        let mut position = None;
+        let mut yaw = 0.0;
+        let mut pitch = 0.0;
+        let mut time_of_day = None;
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            match key.trim() {
+                "seed" => seed = value.trim().parse::<u32>().ok(),
+                "position" => position = parse_vec3(value),
+                "yaw" => yaw = value.trim().parse::<f32>().unwrap_or(0.0),
+                "pitch" => pitch = value.trim().parse::<f32>().unwrap_or(0.0),
+                "time" => time_of_day = value.trim().parse::<f32>().ok(),
+                _ => debug!("Ignoring unknown save key {}", key)
+            }
+        }
+
+        let seed = seed.ok_or_else(|| SaveError::MissingSeed { path: path.to_path_buf() })?;
+
+        Ok(SaveGame {
+            seed,
+            position: position.unwrap_or(Vec3::ZERO),
+            yaw,
+            pitch,
+            time_of_day: time_of_day.unwrap_or(WorldClock::new().time_of_day())
+        })
+    }
+}
+
+pub fn list_saves() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(SAVE_DIRECTORY) else {
+        return Vec::new();
+    };
+
+    let mut saves: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .collect();
+
+    saves.sort();
+    saves
+}
+
+fn parse_vec3(value: &str) -> Option<Vec3> {
+    let mut components = value.split(',').map(|c| c.trim().parse::<f32>());
+    let x = components.next()?.ok()?;
+    let y = components.next()?.ok()?;
+    let z = components.next()?.ok()?;
+    Some(Vec3::new(x, y, z))
+}
@@ -0,0 +1,58 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+use glam::Vec3;
+
+const DEFAULT_DAY_LENGTH_SECONDS: f32 = 1200.0;
+const MORNING: f32 = 0.3;
+
+//Tilts the sun's path away from straight overhead so faces pointing north and south aren't lit identically
+const SUN_PATH_TILT: f32 = 0.35;
+
+//Time of day runs from 0 to 1, with 0.25 sunrise, 0.5 noon and 0.75 sunset
+pub struct WorldClock {
+    time_of_day: f32,
+    day_length_seconds: f32
+}
+
+impl WorldClock {
+    pub fn new() -> Self {
+        Self::at(MORNING)
+    }
+
+    pub fn at(time_of_day: f32) -> Self {
+        Self {
+            time_of_day: time_of_day.rem_euclid(1.0),
+            day_length_seconds: DEFAULT_DAY_LENGTH_SECONDS
+        }
+    }
+
+    pub fn time_of_day(&self) -> f32 {
+        self.time_of_day
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.time_of_day = (self.time_of_day + elapsed.as_secs_f32() / self.day_length_seconds).rem_euclid(1.0);
+    }
+
+    //The sun's angle above the eastern horizon, in radians
+    pub fn sun_angle(&self) -> f32 {
+        (self.time_of_day - 0.25) * TAU
+    }
+
+    //Direction from the surface towards the sun, for a surface whose up is `local_up`. On a flat
+    //world that's always +Y, on a planet it's away from the planet's centre
+    pub fn sun_direction(&self, local_up: Vec3) -> Vec3 {
+        let up = local_up.normalize();
+        let east = up.any_orthonormal_vector();
+        let north = up.cross(east);
+
+        let angle = self.sun_angle();
+        (east * angle.cos() + up * angle.sin() + north * SUN_PATH_TILT).normalize()
+    }
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}